@@ -0,0 +1,141 @@
+//! Receiver gain control
+//!
+//! The A7105 does not adjust its LNA and mixer gain on its own, so a strong signal from a
+//! nearby transmitter can saturate the receiver. [`RxGainMode::Automatic`] enables a
+//! software AGC that inspects the RSSI of every packet received through
+//! [`A7105::receive`](crate::A7105::receive) and steps the front end gain down when the
+//! signal is too strong, and back up when it becomes weak again.
+//!
+//! ```ignore
+//! use a7105::agc::{AgcConfig, RxGainMode};
+//! use a7105::prelude::*;
+//!
+//! # let (a7105_spi_peripheral, mut delay) = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//! radio.set_rx_gain(RxGainMode::Automatic(AgcConfig::default())).await.unwrap();
+//!
+//! let mut buf = [0; 16];
+//! radio.receive(&mut buf, &mut delay, 100_000).await.unwrap();
+//! ```
+
+use crate::registers::{LnaGain, MixerGain, RssiAdcOutput, RxGain1};
+use defmt::Format;
+
+/// How the receiver gain is managed
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub enum RxGainMode {
+    /// The gain is fixed to the provided LNA and mixer gain settings
+    ///
+    /// The VGA gain is not configurable: the datasheet reserves its registers,
+    /// [`RxGain2`](crate::registers::RxGain2) to [`RxGain4`](crate::registers::RxGain4), for
+    /// internal use, and the VGA is only calibrated as selected by
+    /// [`RxGain1::manual_vga_calibration`].
+    Manual(RxGain1),
+    /// The gain is adjusted after every received packet
+    Automatic(AgcConfig),
+}
+
+/// Configuration for the software AGC
+///
+/// The A7105 reports a lower RSSI voltage for a stronger signal. The defaults are rough
+/// figures taken from the typical RSSI characteristic in the datasheet and may need tuning
+/// for a particular board.
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct AgcConfig {
+    /// RSSI voltage below which the gain is reduced by one step
+    pub strong_voltage: f32,
+    /// RSSI voltage above which the gain is increased by one step
+    pub weak_voltage: f32,
+}
+
+impl Default for AgcConfig {
+    fn default() -> Self {
+        Self {
+            strong_voltage: 0.4,
+            weak_voltage: 0.7,
+        }
+    }
+}
+
+/// The state of the software AGC
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct Agc {
+    config: AgcConfig,
+    step: usize,
+}
+
+impl Agc {
+    /// The gain settings the AGC steps through, from the most to the least sensitive
+    ///
+    /// The LNA gain is reduced first as it saturates before the mixer does.
+    pub const STEPS: [(LnaGain, MixerGain); 8] = [
+        (LnaGain::Db24, MixerGain::Db24),
+        (LnaGain::Db18, MixerGain::Db24),
+        (LnaGain::Db12, MixerGain::Db24),
+        (LnaGain::Db6, MixerGain::Db24),
+        (LnaGain::Db0, MixerGain::Db24),
+        (LnaGain::Db0, MixerGain::Db18),
+        (LnaGain::Db0, MixerGain::Db12),
+        (LnaGain::Db0, MixerGain::Db6),
+    ];
+
+    /// Constructs a new [`Agc`] starting from the most sensitive setting
+    pub const fn new(config: AgcConfig) -> Self {
+        Self { config, step: 0 }
+    }
+
+    /// Returns the gain setting the AGC is currently applying
+    pub fn gain(&self) -> RxGain1 {
+        let (lna_gain, mixer_gain) = Self::STEPS[self.step];
+        RxGain1 {
+            lna_gain,
+            mixer_gain,
+            ..Default::default()
+        }
+    }
+
+    /// Feeds the RSSI of a received packet into the AGC, returning the new gain setting if
+    /// it should be changed
+    pub fn update(&mut self, rssi: RssiAdcOutput) -> Option<RxGain1> {
+        let step = if rssi.voltage < self.config.strong_voltage {
+            (self.step + 1).min(Self::STEPS.len() - 1)
+        } else if rssi.voltage > self.config.weak_voltage {
+            self.step.saturating_sub(1)
+        } else {
+            self.step
+        };
+
+        if step == self.step {
+            return None;
+        }
+        self.step = step;
+        Some(self.gain())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_agc_steps() {
+        let mut agc = Agc::new(AgcConfig::default());
+        assert_eq!(agc.gain(), RxGain1::default());
+
+        // A weak signal at full gain leaves the gain alone
+        assert_eq!(agc.update(RssiAdcOutput { voltage: 1.0 }), None);
+
+        // A strong signal walks the gain down until the minimum is reached
+        for _ in 1..Agc::STEPS.len() {
+            assert!(agc.update(RssiAdcOutput { voltage: 0.1 }).is_some());
+        }
+        assert_eq!(agc.update(RssiAdcOutput { voltage: 0.1 }), None);
+        assert_eq!(agc.gain().lna_gain, LnaGain::Db0);
+        assert_eq!(agc.gain().mixer_gain, MixerGain::Db6);
+
+        // Signals within the window are left alone, weaker ones step the gain back up
+        assert_eq!(agc.update(RssiAdcOutput { voltage: 0.5 }), None);
+        let gain = agc.update(RssiAdcOutput { voltage: 0.9 }).unwrap();
+        assert_eq!(gain.mixer_gain, MixerGain::Db12);
+    }
+}
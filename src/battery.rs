@@ -0,0 +1,231 @@
+//! Battery voltage monitoring built on the battery detector of the A7105
+//!
+//! The detector only reports whether the supply is above a single
+//! [`DetectThreshold`], so [`A7105::measure_battery_voltage`] brackets the supply voltage by
+//! searching across all eight thresholds, while a [`BatteryWatcher`] repeatedly checks a
+//! single threshold and reports when the supply crosses it.
+//!
+//! ```ignore
+//! use a7105::battery::{BatteryEvent, BatteryWatcher};
+//! use a7105::prelude::*;
+//!
+//! # let (a7105_spi_peripheral, mut delay) = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//!
+//! let range = radio.measure_battery_voltage(&mut delay).await.unwrap();
+//!
+//! let mut watcher = BatteryWatcher::new(registers::DetectThreshold::V22);
+//! match watcher.watch(&mut radio, &mut delay, 60_000_000).await.unwrap() {
+//!     BatteryEvent::Low => {}
+//!     BatteryEvent::Recovered => {}
+//! }
+//! ```
+
+use crate::{registers::DetectThreshold, A7105};
+use defmt::Format;
+
+#[cfg(feature = "blocking")]
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs, spi::SpiDevice};
+
+/// The range the supply voltage was found to be in
+///
+/// The supply is at or above `above` and below `below`, where `None` means the supply is
+/// outside of the range the detector can measure.
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct VoltageRange {
+    /// The highest threshold the supply exceeds
+    pub above: Option<DetectThreshold>,
+    /// The lowest threshold the supply does not exceed
+    pub below: Option<DetectThreshold>,
+}
+
+impl VoltageRange {
+    /// Constructs the range for a supply exceeding the lowest `count` thresholds
+    pub(crate) fn from_count(count: usize) -> Self {
+        Self {
+            above: count
+                .checked_sub(1)
+                .and_then(|i| DetectThreshold::ALL.get(i))
+                .copied(),
+            below: DetectThreshold::ALL.get(count).copied(),
+        }
+    }
+
+    /// Returns the lower bound of the supply voltage in millivolts, if known
+    pub fn min_millivolts(&self) -> Option<u16> {
+        self.above.map(|threshold| threshold.millivolts())
+    }
+
+    /// Returns the upper bound of the supply voltage in millivolts, if known
+    pub fn max_millivolts(&self) -> Option<u16> {
+        self.below.map(|threshold| threshold.millivolts())
+    }
+}
+
+/// A change in battery state reported by a [`BatteryWatcher`]
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub enum BatteryEvent {
+    /// The supply dropped below the watched threshold
+    Low,
+    /// The supply rose back above the watched threshold
+    Recovered,
+}
+
+/// Watches the supply voltage against a single threshold
+pub struct BatteryWatcher {
+    threshold: DetectThreshold,
+    low: Option<bool>,
+}
+
+impl BatteryWatcher {
+    /// Constructs a new [`BatteryWatcher`] for the given threshold
+    pub const fn new(threshold: DetectThreshold) -> Self {
+        Self {
+            threshold,
+            low: None,
+        }
+    }
+
+    /// Returns whether the supply was below the threshold at the last check, or `None` if
+    /// it has not been checked yet
+    pub fn is_low(&self) -> Option<bool> {
+        self.low
+    }
+
+    fn update(&mut self, low: bool) -> Option<BatteryEvent> {
+        let previous = self.low.replace(low);
+        match (previous, low) {
+            (Some(false) | None, true) => Some(BatteryEvent::Low),
+            (Some(true), false) => Some(BatteryEvent::Recovered),
+            _ => None,
+        }
+    }
+
+    /// Checks the supply once, returning an event if it crossed the threshold since the
+    /// last check
+    ///
+    /// A supply found to be low on the very first check is reported as
+    /// [`BatteryEvent::Low`]. Refer to [`A7105::battery_above`] for the required radio
+    /// state.
+    #[maybe_async::maybe_async]
+    pub async fn check<SPI: SpiDevice, D: DelayNs>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+    ) -> Result<Option<BatteryEvent>, SPI::Error> {
+        let above = radio.battery_above(self.threshold, delay).await?;
+        Ok(self.update(!above))
+    }
+
+    /// Checks the supply every `interval_us` microseconds until it crosses the threshold
+    #[maybe_async::maybe_async]
+    pub async fn watch<SPI: SpiDevice, D: DelayNs>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        interval_us: u32,
+    ) -> Result<BatteryEvent, SPI::Error> {
+        loop {
+            if let Some(event) = self.check(radio, delay).await? {
+                return Ok(event);
+            }
+            delay.delay_us(interval_us).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::{run, MockDelay, MockSpi};
+    use crate::registers::{BatteryDetectConfig, NonSleepModeVoltageSetting};
+
+    #[test]
+    fn test_voltage_range() {
+        let range = VoltageRange::from_count(0);
+        assert_eq!(range.min_millivolts(), None);
+        assert_eq!(range.max_millivolts(), Some(2000));
+
+        let range = VoltageRange::from_count(3);
+        assert_eq!(range.above, Some(DetectThreshold::V22));
+        assert_eq!(range.below, Some(DetectThreshold::V23));
+
+        let range = VoltageRange::from_count(8);
+        assert_eq!(range.min_millivolts(), Some(2700));
+        assert_eq!(range.max_millivolts(), None);
+    }
+
+    #[test]
+    fn test_watcher_transitions() {
+        let mut watcher = BatteryWatcher::new(DetectThreshold::V22);
+        assert_eq!(watcher.update(false), None);
+        assert_eq!(watcher.update(true), Some(BatteryEvent::Low));
+        assert_eq!(watcher.update(true), None);
+        assert_eq!(watcher.update(false), Some(BatteryEvent::Recovered));
+        assert_eq!(watcher.is_low(), Some(false));
+
+        let mut watcher = BatteryWatcher::new(DetectThreshold::V22);
+        assert_eq!(watcher.update(true), Some(BatteryEvent::Low));
+    }
+
+    #[test]
+    fn test_measure_battery_voltage() {
+        for (supply_mv, count) in [(1_900, 0), (2_000, 1), (2_350, 4), (2_600, 7), (3_300, 8)] {
+            let spi = MockSpi::new();
+            spi.sim().supply_mv = supply_mv;
+            let mut radio = A7105::new(spi.clone());
+            let mut delay = MockDelay::default();
+
+            let range = run!(radio.measure_battery_voltage(&mut delay)).unwrap();
+            assert_eq!(range, VoltageRange::from_count(count));
+
+            let detections = spi
+                .sim()
+                .transactions
+                .iter()
+                .filter(|bytes| bytes[0] == 0x27)
+                .count();
+            assert!(detections <= 4);
+        }
+    }
+
+    #[test]
+    fn test_battery_above_keeps_regulator_settings() {
+        let spi = MockSpi::new();
+        let mut radio = A7105::new(spi.clone());
+        let mut delay = MockDelay::default();
+        radio.enable_shadow();
+        let regulator = BatteryDetectConfig {
+            nonsleep_voltage_setting: NonSleepModeVoltageSetting::V18,
+            ..Default::default()
+        };
+        run!(radio.write_reg(regulator)).unwrap();
+
+        assert!(run!(radio.battery_above(DetectThreshold::V25, &mut delay)).unwrap());
+        let sim = spi.sim();
+        let written = sim
+            .transactions
+            .iter()
+            .rfind(|bytes| bytes[0] == 0x27)
+            .unwrap();
+        let expected = u8::from(BatteryDetectConfig {
+            detect_threshold: DetectThreshold::V25,
+            detect_enabled: true,
+            ..regulator
+        });
+        assert_eq!(written[1..], [expected]);
+        drop(sim);
+
+        let shadow = radio.shadow().unwrap();
+        let cached: BatteryDetectConfig = shadow.get().unwrap();
+        assert_eq!(
+            cached,
+            BatteryDetectConfig {
+                detect_threshold: DetectThreshold::V25,
+                ..regulator
+            }
+        );
+    }
+}
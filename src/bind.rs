@@ -0,0 +1,516 @@
+//! A pairing handshake that lets two radios agree on a private sync word and hop seed
+//!
+//! Before binding, both radios use a well-known [`BindConfig::bind_id`] sync word on a
+//! well-known [`BindConfig::bind_channel`]. The initiator generates a random [`Binding`] and
+//! runs a three way handshake with the responder:
+//!
+//! 1. The initiator repeatedly **announces** the binding until the responder replies
+//! 2. The responder **responds** by echoing the binding back
+//! 3. The initiator **confirms** the binding, after which the responder accepts it
+//!
+//! Once the handshake completes, both sides persist the binding through a [`BindingStore`]
+//! and switch their [`IdData`] to the newly agreed sync word.
+//!
+//! A responder that misses the confirmation keeps responding, so the initiator repeats the
+//! confirmation until it hears no response for two response timeouts, and only then
+//! persists the binding. Like any handshake over a lossy link this can not rule out
+//! disagreement entirely: if every response in that window is lost as well, the initiator
+//! persists a binding which the responder gives up on with [`BindError::Timeout`]. The
+//! application should then bind again.
+//!
+//! ```ignore
+//! use a7105::bind::{BindConfig, Binder};
+//! use a7105::prelude::*;
+//!
+//! # let (a7105_spi_peripheral, mut delay, mut rng, mut flash) = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//! let binder: Binder<16> = Binder::new(BindConfig::default());
+//!
+//! let binding = match flash.load().unwrap() {
+//!     Some(binding) => binding,
+//!     None => binder
+//!         .initiate(&mut radio, &mut delay, &mut rng, &mut flash)
+//!         .await
+//!         .unwrap(),
+//! };
+//! ```
+
+use crate::{registers::IdData, registers::Pll1, BindError, ReadPacketError, A7105};
+use defmt::Format;
+use rand_core::RngCore;
+
+#[cfg(feature = "blocking")]
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs, spi::SpiDevice};
+
+const KIND_ANNOUNCE: u8 = 0xB1;
+const KIND_RESPOND: u8 = 0xB2;
+const KIND_CONFIRM: u8 = 0xB3;
+const FRAME_LEN: usize = 1 + Binding::LEN;
+
+/// The link parameters agreed upon by two bound radios
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct Binding {
+    /// The sync word used for all further communication
+    pub id: IdData,
+    /// A seed the application may use to derive its hopping sequence
+    pub hop_seed: u32,
+}
+
+impl Binding {
+    /// The length of the serialised binding, in bytes
+    pub const LEN: usize = 8;
+
+    /// Generates a new random binding
+    pub fn generate<R: RngCore>(rng: &mut R) -> Self {
+        Self {
+            id: IdData::generate(rng),
+            hop_seed: rng.next_u32(),
+        }
+    }
+
+    /// Serialises the binding, for example to persist it to flash
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut bytes = [0; Self::LEN];
+        bytes[..4].copy_from_slice(&self.id.id.to_le_bytes());
+        bytes[4..].copy_from_slice(&self.hop_seed.to_le_bytes());
+        bytes
+    }
+
+    /// Deserialises a binding previously serialised with [`Binding::to_bytes`]
+    pub fn from_bytes(bytes: [u8; Self::LEN]) -> Self {
+        let [a, b, c, d, e, f, g, h] = bytes;
+        Self {
+            id: IdData {
+                id: u32::from_le_bytes([a, b, c, d]),
+            },
+            hop_seed: u32::from_le_bytes([e, f, g, h]),
+        }
+    }
+}
+
+/// Persistent storage for a [`Binding`], such as a page of flash
+pub trait BindingStore {
+    /// The error type returned by the store
+    type Error;
+
+    /// Loads the stored binding, if any
+    fn load(&mut self) -> Result<Option<Binding>, Self::Error>;
+
+    /// Stores the binding, replacing any previously stored binding
+    fn store(&mut self, binding: &Binding) -> Result<(), Self::Error>;
+}
+
+/// Configuration for a [`Binder`], which must be identical on both radios
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct BindConfig {
+    /// The well-known sync word used during the handshake
+    pub bind_id: IdData,
+    /// The well-known [`Pll1`] channel used during the handshake
+    pub bind_channel: u8,
+    /// How long to wait for each reply from the peer, in microseconds
+    pub response_timeout_us: u32,
+    /// The number of times a handshake message is sent before giving up
+    pub attempts: u16,
+}
+
+impl Default for BindConfig {
+    fn default() -> Self {
+        Self {
+            bind_id: IdData { id: 0x2A27_5A54 },
+            bind_channel: 0,
+            response_timeout_us: 20_000,
+            attempts: 250,
+        }
+    }
+}
+
+/// Writes a handshake message of the given kind into `frame`
+fn encode(kind: u8, binding: &Binding, frame: &mut [u8]) {
+    frame.fill(0);
+    frame[0] = kind;
+    frame[1..FRAME_LEN].copy_from_slice(&binding.to_bytes());
+}
+
+/// Parses a received handshake message, returning its kind and binding
+fn decode(frame: &[u8]) -> Option<(u8, Binding)> {
+    let kind = *frame.first()?;
+    let mut bytes = [0; Binding::LEN];
+    bytes.copy_from_slice(frame.get(1..FRAME_LEN)?);
+    Some((kind, Binding::from_bytes(bytes)))
+}
+
+/// Runs the bind handshake using `N` byte packets, where `N` must be at least 9, which is
+/// checked at compile time
+pub struct Binder<const N: usize> {
+    config: BindConfig,
+}
+
+impl<const N: usize> Binder<N> {
+    /// Fails to compile when a handshake message does not fit into an `N` byte packet
+    const FRAME_FITS: () = assert!(N >= FRAME_LEN, "Binder packets must be at least 9 bytes");
+
+    /// Constructs a new [`Binder`] with the provided configuration
+    ///
+    /// Using packets shorter than 9 bytes is a compile time error.
+    pub const fn new(config: BindConfig) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::FRAME_FITS;
+        Self { config }
+    }
+
+    /// Switches the radio to the well-known bind sync word and channel
+    #[maybe_async::maybe_async]
+    async fn enter_bind<SPI: SpiDevice>(&self, radio: &mut A7105<SPI>) -> Result<(), SPI::Error> {
+        radio.write_reg(self.config.bind_id).await?;
+        radio
+            .write_reg(Pll1 {
+                channel: self.config.bind_channel,
+            })
+            .await
+    }
+
+    /// Persists the binding and switches the radio to its sync word
+    #[maybe_async::maybe_async]
+    async fn accept<SPI: SpiDevice, S: BindingStore>(
+        &self,
+        radio: &mut A7105<SPI>,
+        store: &mut S,
+        binding: Binding,
+    ) -> Result<Binding, BindError<SPI::Error, S::Error>> {
+        store.store(&binding).map_err(BindError::StoreError)?;
+        radio.write_reg(binding.id).await?;
+        Ok(binding)
+    }
+
+    /// Waits up to `timeout_us` microseconds for a handshake message of the given kind,
+    /// ignoring anything else
+    #[maybe_async::maybe_async]
+    async fn expect<SPI: SpiDevice, D: DelayNs>(
+        &self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        kinds: &[u8],
+        timeout_us: u32,
+    ) -> Result<Option<(u8, Binding)>, SPI::Error> {
+        let mut frame = [0; N];
+        match radio.receive(&mut frame, delay, timeout_us).await {
+            Ok(()) => Ok(decode(&frame).filter(|(kind, _)| kinds.contains(kind))),
+            Err(ReadPacketError::SpiError(e)) => Err(e),
+            Err(ReadPacketError::PacketError(_) | ReadPacketError::Timeout) => Ok(None),
+        }
+    }
+
+    /// Generates a new binding and runs the initiating side of the handshake, persisting
+    /// the binding once the responder has acknowledged it
+    ///
+    /// The confirmation is repeated for as long as the responder keeps responding, up to
+    /// [`BindConfig::attempts`] times, as described in the [module level
+    /// documentation](self).
+    #[maybe_async::maybe_async]
+    pub async fn initiate<SPI: SpiDevice, D: DelayNs, R: RngCore, S: BindingStore>(
+        &self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        rng: &mut R,
+        store: &mut S,
+    ) -> Result<Binding, BindError<SPI::Error, S::Error>> {
+        let binding = Binding::generate(rng);
+        let mut frame = [0; N];
+        self.enter_bind(radio).await?;
+
+        let timeout_us = self.config.response_timeout_us;
+        let mut responded = false;
+        for _ in 0..self.config.attempts {
+            encode(KIND_ANNOUNCE, &binding, &mut frame);
+            radio.transmit(&frame, delay).await?;
+
+            if let Some((_, echoed)) = self
+                .expect(radio, delay, &[KIND_RESPOND], timeout_us)
+                .await?
+            {
+                if echoed == binding {
+                    responded = true;
+                    break;
+                }
+            }
+        }
+        if !responded {
+            return Err(BindError::Timeout);
+        }
+
+        // The responder retries after its own response timeout, so listen for longer than
+        // that before deciding the confirmation was heard
+        encode(KIND_CONFIRM, &binding, &mut frame);
+        for _ in 0..self.config.attempts {
+            radio.transmit(&frame, delay).await?;
+            match self
+                .expect(radio, delay, &[KIND_RESPOND], timeout_us.saturating_mul(2))
+                .await?
+            {
+                Some((_, echoed)) if echoed == binding => continue,
+                _ => return self.accept(radio, store, binding).await,
+            }
+        }
+
+        Err(BindError::Timeout)
+    }
+
+    /// Runs the responding side of the handshake, persisting the binding announced by the
+    /// initiator once it has been confirmed
+    ///
+    /// This waits up to `timeout_us` microseconds for an announcement to arrive. Corrupt
+    /// packets and other handshake messages are ignored, without extending the wait.
+    #[maybe_async::maybe_async]
+    pub async fn respond<SPI: SpiDevice, D: DelayNs, S: BindingStore>(
+        &self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        store: &mut S,
+        timeout_us: u32,
+    ) -> Result<Binding, BindError<SPI::Error, S::Error>> {
+        let mut frame = [0; N];
+        self.enter_bind(radio).await?;
+
+        let mut remaining_us = timeout_us;
+        let mut binding = loop {
+            match radio
+                .receive_within(&mut frame, delay, &mut remaining_us)
+                .await
+            {
+                Ok(()) => {}
+                Err(ReadPacketError::SpiError(e)) => return Err(e.into()),
+                Err(ReadPacketError::PacketError(_)) => continue,
+                Err(ReadPacketError::Timeout) => return Err(BindError::Timeout),
+            }
+            if let Some((KIND_ANNOUNCE, binding)) = decode(&frame) {
+                break binding;
+            }
+        };
+
+        for _ in 0..self.config.attempts {
+            encode(KIND_RESPOND, &binding, &mut frame);
+            radio.transmit(&frame, delay).await?;
+
+            match self
+                .expect(
+                    radio,
+                    delay,
+                    &[KIND_ANNOUNCE, KIND_CONFIRM],
+                    self.config.response_timeout_us,
+                )
+                .await?
+            {
+                Some((KIND_CONFIRM, confirmed)) if confirmed == binding => {
+                    return self.accept(radio, store, binding).await;
+                }
+                // The initiator did not hear our response, or restarted with a new binding
+                Some((KIND_ANNOUNCE, announced)) => binding = announced,
+                _ => continue,
+            }
+        }
+
+        Err(BindError::Timeout)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::*;
+    use crate::mock::{run, MockDelay, MockSpi};
+    use core::convert::Infallible;
+    use std::{boxed::Box, vec::Vec};
+
+    /// A xorshift generator, deterministic for the tests
+    struct TestRng(u32);
+
+    impl RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            self.0
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            u64::from(self.next_u32()) << 32 | u64::from(self.next_u32())
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            rand_core::impls::fill_bytes_via_next(self, dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryStore(Option<Binding>);
+
+    impl BindingStore for MemoryStore {
+        type Error = Infallible;
+
+        fn load(&mut self) -> Result<Option<Binding>, Infallible> {
+            Ok(self.0)
+        }
+
+        fn store(&mut self, binding: &Binding) -> Result<(), Infallible> {
+            self.0 = Some(*binding);
+            Ok(())
+        }
+    }
+
+    const CONFIG: BindConfig = BindConfig {
+        bind_id: IdData { id: 0x2A27_5A54 },
+        bind_channel: 0,
+        response_timeout_us: 1_000,
+        attempts: 4,
+    };
+
+    fn message(kind: u8, binding: &Binding) -> Vec<u8> {
+        let mut frame = [0; 9];
+        encode(kind, binding, &mut frame);
+        frame.to_vec()
+    }
+
+    fn kinds(spi: &MockSpi) -> Vec<u8> {
+        spi.sim().sent.iter().map(|frame| frame[0]).collect()
+    }
+
+    /// Answers every announcement with a response, the first `missed` confirmations with
+    /// another response, and nothing else
+    fn responder(mut missed: usize) -> crate::mock::Responder {
+        Box::new(move |frame| match decode(frame)? {
+            (KIND_ANNOUNCE, binding) => Some(message(KIND_RESPOND, &binding)),
+            (KIND_CONFIRM, binding) if missed > 0 => {
+                missed -= 1;
+                Some(message(KIND_RESPOND, &binding))
+            }
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_binding_round_trip() {
+        let binding = Binding {
+            id: IdData { id: 0x1234_56A7 },
+            hop_seed: 0xDEAD_BEEF,
+        };
+        assert_eq!(Binding::from_bytes(binding.to_bytes()), binding);
+
+        let mut frame = [0xFF; 12];
+        encode(KIND_CONFIRM, &binding, &mut frame);
+        assert_eq!(decode(&frame), Some((KIND_CONFIRM, binding)));
+        assert_eq!(decode(&frame[..4]), None);
+    }
+
+    #[test]
+    fn test_initiate() {
+        let binder: Binder<9> = Binder::new(CONFIG);
+        let mut delay = MockDelay::default();
+        let mut store = MemoryStore::default();
+
+        let spi = MockSpi::new();
+        spi.sim().responder = Some(responder(0));
+        let mut radio = A7105::new(spi.clone());
+        let binding = run!(binder.initiate(&mut radio, &mut delay, &mut TestRng(1), &mut store));
+        let binding = binding.unwrap();
+        assert_eq!(store.0, Some(binding));
+        assert_eq!(kinds(&spi)[..], [KIND_ANNOUNCE, KIND_CONFIRM]);
+        // The radio is switched to the agreed sync word
+        let last = spi.sim().transactions.last().unwrap().clone();
+        assert_eq!(last[0], 0x06);
+        assert_eq!(last[1..], binding.id.id.to_le_bytes());
+
+        // The confirmation is repeated while the responder keeps responding
+        let spi = MockSpi::new();
+        spi.sim().responder = Some(responder(2));
+        let mut radio = A7105::new(spi.clone());
+        let result = run!(binder.initiate(&mut radio, &mut delay, &mut TestRng(2), &mut store));
+        assert_eq!(store.0, Some(result.unwrap()));
+        assert_eq!(
+            kinds(&spi)[..],
+            [KIND_ANNOUNCE, KIND_CONFIRM, KIND_CONFIRM, KIND_CONFIRM]
+        );
+    }
+
+    #[test]
+    fn test_initiate_timeout() {
+        let binder: Binder<9> = Binder::new(CONFIG);
+        let mut delay = MockDelay::default();
+        let mut store = MemoryStore::default();
+
+        // Nobody answers, so every announcement is sent and nothing is stored
+        let spi = MockSpi::new();
+        let mut radio = A7105::new(spi.clone());
+        let result = run!(binder.initiate(&mut radio, &mut delay, &mut TestRng(1), &mut store));
+        assert_eq!(result, Err(BindError::Timeout));
+        assert_eq!(kinds(&spi)[..], [KIND_ANNOUNCE; 4]);
+        assert_eq!(store.0, None);
+
+        // A responder that never hears the confirmation is never bound to
+        let spi = MockSpi::new();
+        spi.sim().responder = Some(responder(usize::MAX));
+        let mut radio = A7105::new(spi.clone());
+        let result = run!(binder.initiate(&mut radio, &mut delay, &mut TestRng(1), &mut store));
+        assert_eq!(result, Err(BindError::Timeout));
+        assert_eq!(store.0, None);
+    }
+
+    #[test]
+    fn test_respond() {
+        let binder: Binder<9> = Binder::new(CONFIG);
+        let mut delay = MockDelay::default();
+        let mut store = MemoryStore::default();
+        let binding = Binding::generate(&mut TestRng(3));
+
+        // The first response is lost, so the responder responds again
+        let spi = MockSpi::new();
+        spi.sim().push_corrupt(&message(KIND_ANNOUNCE, &binding));
+        spi.sim().push(&message(KIND_ANNOUNCE, &binding));
+        let mut responses = 0;
+        spi.sim().responder = Some(Box::new(move |frame| {
+            responses += 1;
+            let (_, binding) = decode(frame)?;
+            (responses > 1).then(|| message(KIND_CONFIRM, &binding))
+        }));
+        let mut radio = A7105::new(spi.clone());
+        let result = run!(binder.respond(&mut radio, &mut delay, &mut store, 1_000));
+        assert_eq!(result, Ok(binding));
+        assert_eq!(store.0, Some(binding));
+        assert_eq!(kinds(&spi)[..], [KIND_RESPOND, KIND_RESPOND]);
+    }
+
+    #[test]
+    fn test_respond_timeout() {
+        let binder: Binder<9> = Binder::new(CONFIG);
+        let mut delay = MockDelay::default();
+        let mut store = MemoryStore::default();
+        let binding = Binding::generate(&mut TestRng(3));
+
+        // Other handshake messages keep arriving, but do not restart the timeout
+        let spi = MockSpi::new();
+        spi.sim().airtime_polls = 5;
+        for _ in 0..100 {
+            spi.sim().push(&message(KIND_CONFIRM, &binding));
+        }
+        let mut radio = A7105::new(spi.clone());
+        let result = run!(binder.respond(&mut radio, &mut delay, &mut store, 1_000));
+        assert_eq!(result, Err(BindError::Timeout));
+        assert_eq!(delay.elapsed_us, 1_000);
+
+        // The confirmation never arrives
+        let spi = MockSpi::new();
+        spi.sim().push(&message(KIND_ANNOUNCE, &binding));
+        let mut radio = A7105::new(spi.clone());
+        let result = run!(binder.respond(&mut radio, &mut delay, &mut store, 1_000));
+        assert_eq!(result, Err(BindError::Timeout));
+        assert_eq!(kinds(&spi)[..], [KIND_RESPOND; 4]);
+        assert_eq!(store.0, None);
+    }
+}
@@ -0,0 +1,275 @@
+//! A ready-made radio task for [Embassy](https://embassy.dev)
+//!
+//! A [`RadioRunner`] owns the [`A7105`] and is run as a dedicated task, serialising every
+//! access to the radio. The rest of the application talks to it through any number of
+//! [`RadioHandle`]s, which queue packets for transmission and collect received packets
+//! through [`embassy_sync::channel::Channel`]s held in a shared [`RadioState`].
+//!
+//! The runner keeps the A7105 in [`Mode::Rx`] whenever it has nothing to send, checking it
+//! for a received packet every 100µs. A queued packet wakes the runner immediately and is
+//! transmitted between receptions, after which the A7105 is placed back in [`Mode::Rx`].
+//! Packets are always a fixed `N` bytes long, matching the FIFO length configured through
+//! [`Fifo1`](crate::registers::Fifo1). Received packets that fail their CRC or FEC checks
+//! are dropped, as are valid packets received while the receive queue is full.
+//!
+//! Embassy tasks can not be generic, so the runner is driven from a small task written
+//! for the concrete types of the application:
+//!
+//! ```ignore
+//! use a7105::embassy::{RadioRunner, RadioState};
+//! use a7105::prelude::*;
+//! use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+//!
+//! static RADIO: RadioState<CriticalSectionRawMutex, 16, 4> = RadioState::new();
+//!
+//! #[embassy_executor::task]
+//! async fn radio_task(radio: A7105<MySpi>, delay: MyDelay) {
+//!     let mut runner = RadioRunner::new(&RADIO, radio, delay);
+//!     runner.run().await.unwrap();
+//! }
+//!
+//! # let (spawner, a7105_spi_peripheral, delay) = unimplemented!();
+//! spawner.spawn(radio_task(A7105::new(a7105_spi_peripheral), delay)).unwrap();
+//!
+//! let radio = RADIO.handle();
+//! radio.send(*b"hello, world!\0\0\0").await;
+//! let packet = radio.recv().await;
+//! ```
+
+use crate::{commands::Mode, ReadPacketError, A7105};
+use core::{
+    convert::Infallible,
+    future::{poll_fn, Future},
+    pin::pin,
+    task::Poll,
+};
+use embassy_sync::{blocking_mutex::raw::RawMutex, channel::Channel};
+use embedded_hal_async::{delay::DelayNs, spi::SpiDevice};
+
+/// The queues shared between a [`RadioRunner`] and its [`RadioHandle`]s, holding up to
+/// `DEPTH` packets of `N` bytes in each direction
+pub struct RadioState<M: RawMutex, const N: usize, const DEPTH: usize> {
+    tx: Channel<M, [u8; N], DEPTH>,
+    rx: Channel<M, [u8; N], DEPTH>,
+}
+
+impl<M: RawMutex, const N: usize, const DEPTH: usize> RadioState<M, N, DEPTH> {
+    /// Constructs a new [`RadioState`] with empty queues
+    pub const fn new() -> Self {
+        Self {
+            tx: Channel::new(),
+            rx: Channel::new(),
+        }
+    }
+
+    /// Returns a new [`RadioHandle`] to the radio
+    pub fn handle(&self) -> RadioHandle<'_, M, N, DEPTH> {
+        RadioHandle { state: self }
+    }
+}
+
+impl<M: RawMutex, const N: usize, const DEPTH: usize> Default for RadioState<M, N, DEPTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A handle for sending and receiving packets through a [`RadioRunner`]
+///
+/// Handles are cheap to copy and can be shared between tasks. Every received packet is
+/// delivered to exactly one of the handles waiting in [`RadioHandle::recv`].
+pub struct RadioHandle<'a, M: RawMutex, const N: usize, const DEPTH: usize> {
+    state: &'a RadioState<M, N, DEPTH>,
+}
+
+impl<M: RawMutex, const N: usize, const DEPTH: usize> Clone for RadioHandle<'_, M, N, DEPTH> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M: RawMutex, const N: usize, const DEPTH: usize> Copy for RadioHandle<'_, M, N, DEPTH> {}
+
+impl<M: RawMutex, const N: usize, const DEPTH: usize> RadioHandle<'_, M, N, DEPTH> {
+    /// Queues a packet for transmission, waiting for room in the queue if it is full
+    ///
+    /// This returns once the packet has been queued, not once it has been transmitted.
+    pub async fn send(&self, packet: [u8; N]) {
+        self.state.tx.send(packet).await
+    }
+
+    /// Queues a packet for transmission, returning the packet if the queue is full
+    pub fn try_send(&self, packet: [u8; N]) -> Result<(), [u8; N]> {
+        self.state.tx.try_send(packet).map_err(|e| match e {
+            embassy_sync::channel::TrySendError::Full(packet) => packet,
+        })
+    }
+
+    /// Waits for the next received packet
+    pub async fn recv(&self) -> [u8; N] {
+        self.state.rx.receive().await
+    }
+
+    /// Returns the next received packet, or `None` if no packet has been received
+    pub fn try_recv(&self) -> Option<[u8; N]> {
+        self.state.rx.try_receive().ok()
+    }
+}
+
+/// The task side of a [`RadioState`], owning the [`A7105`]
+///
+/// Refer to the [module level documentation](self) for an overview.
+pub struct RadioRunner<'a, M: RawMutex, SPI, D, const N: usize, const DEPTH: usize> {
+    state: &'a RadioState<M, N, DEPTH>,
+    radio: A7105<SPI>,
+    delay: D,
+}
+
+impl<'a, M, SPI, D, const N: usize, const DEPTH: usize> RadioRunner<'a, M, SPI, D, N, DEPTH>
+where
+    M: RawMutex,
+    SPI: SpiDevice,
+    D: DelayNs,
+{
+    /// The interval at which the A7105 is polled for a received packet
+    const POLL_INTERVAL_US: u32 = 100;
+
+    /// Constructs a new [`RadioRunner`] serving the handles of the provided [`RadioState`]
+    ///
+    /// The A7105 must already be configured, and is not touched until
+    /// [`RadioRunner::run`] is called.
+    pub fn new(state: &'a RadioState<M, N, DEPTH>, radio: A7105<SPI>, delay: D) -> Self {
+        Self {
+            state,
+            radio,
+            delay,
+        }
+    }
+
+    /// Destroys this [`RadioRunner`], returning the radio and delay
+    pub fn release(self) -> (A7105<SPI>, D) {
+        (self.radio, self.delay)
+    }
+
+    /// Serves the handles until a SPI error is encountered
+    ///
+    /// The A7105 may be left in any mode once this returns.
+    pub async fn run(&mut self) -> Result<Infallible, SPI::Error> {
+        let mut buf = [0; N];
+        self.radio.set_mode(Mode::Rx).await?;
+        loop {
+            if let Some(packet) = self.next_packet().await {
+                // Abandon any packet that may be part way through being received
+                self.radio.set_mode(Mode::Standby).await?;
+                self.radio.transmit(&packet, &mut self.delay).await?;
+                self.radio.set_mode(Mode::Rx).await?;
+                continue;
+            }
+
+            if self.radio.is_busy().await? {
+                continue;
+            }
+
+            match self.radio.rx(&mut buf).await {
+                Ok(()) => {
+                    // A full queue drops the packet rather than stalling transmissions
+                    let _ = self.state.rx.try_send(buf);
+                }
+                Err(ReadPacketError::SpiError(e)) => return Err(e),
+                Err(ReadPacketError::PacketError(_) | ReadPacketError::Timeout) => {}
+            }
+            self.radio.set_mode(Mode::Rx).await?;
+        }
+    }
+
+    /// Waits up to the poll interval for a packet to be queued, returning `None` once the
+    /// A7105 is due to be polled
+    async fn next_packet(&mut self) -> Option<[u8; N]> {
+        let mut queued = pin!(self.state.tx.receive());
+        let mut poll = pin!(self.delay.delay_us(Self::POLL_INTERVAL_US));
+        poll_fn(|cx| {
+            if let Poll::Ready(packet) = queued.as_mut().poll(cx) {
+                return Poll::Ready(Some(packet));
+            }
+            poll.as_mut().poll(cx).map(|()| None)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::{poll_times, MockDelay, MockSpi};
+    use core::pin::pin;
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+
+    #[test]
+    fn test_handle_queues() {
+        let state: RadioState<NoopRawMutex, 2, 1> = RadioState::new();
+        let handle = state.handle();
+        let other = handle;
+
+        assert_eq!(handle.try_send([1, 2]), Ok(()));
+        assert_eq!(other.try_send([3, 4]), Err([3, 4]));
+        assert_eq!(state.tx.try_receive().ok(), Some([1, 2]));
+
+        assert_eq!(handle.try_recv(), None);
+        state.rx.try_send([5, 6]).unwrap();
+        assert_eq!(other.try_recv(), Some([5, 6]));
+        assert_eq!(handle.try_recv(), None);
+    }
+
+    #[test]
+    fn test_runner() {
+        let state: RadioState<NoopRawMutex, 3, 2> = RadioState::new();
+        let handle = state.handle();
+        let spi = MockSpi::new();
+        let mut runner = RadioRunner::new(&state, A7105::new(spi.clone()), MockDelay::default());
+
+        spi.sim().push(&[1, 2, 3]);
+        spi.sim().push_corrupt(&[9, 9, 9]);
+        handle.try_send([4, 5, 6]).unwrap();
+        assert!(poll_times(pin!(runner.run()), 20).is_none());
+
+        // The queued packet is sent, the valid packet delivered and the corrupt one dropped
+        let sim = spi.sim();
+        assert_eq!(sim.sent.len(), 1);
+        assert_eq!(sim.sent[0][..], [4, 5, 6]);
+        assert!(sim.in_rx());
+        drop(sim);
+        assert_eq!(handle.try_recv(), Some([1, 2, 3]));
+        assert_eq!(handle.try_recv(), None);
+
+        // The runner keeps serving the handles once the radio is idle
+        handle.try_send([7, 8, 9]).unwrap();
+        assert!(poll_times(pin!(runner.run()), 20).is_none());
+        assert_eq!(spi.sim().sent.len(), 2);
+    }
+
+    /// A delay that never elapses
+    struct StalledDelay;
+
+    impl DelayNs for StalledDelay {
+        async fn delay_ns(&mut self, _ns: u32) {
+            core::future::pending().await
+        }
+    }
+
+    #[test]
+    fn test_runner_wakes_for_queued_packet() {
+        let state: RadioState<NoopRawMutex, 3, 2> = RadioState::new();
+        let handle = state.handle();
+        let spi = MockSpi::new();
+        let mut runner = RadioRunner::new(&state, A7105::new(spi.clone()), StalledDelay);
+        let mut run = pin!(runner.run());
+
+        assert!(poll_times(run.as_mut(), 3).is_none());
+        assert!(spi.sim().sent.is_empty());
+
+        // A queued packet is transmitted without waiting for the poll interval to elapse
+        handle.try_send([4, 5, 6]).unwrap();
+        assert!(poll_times(run.as_mut(), 1).is_none());
+        assert_eq!(spi.sim().sent.len(), 1);
+    }
+}
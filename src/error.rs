@@ -1,3 +1,4 @@
+use core::convert::Infallible;
 use defmt::Format;
 
 use crate::registers::Mode;
@@ -5,11 +6,14 @@ use crate::registers::Mode;
 /// An error that can result from the attempt to receive a packet with
 /// the A7105
 #[derive(Format, PartialEq, Debug, Clone)]
+#[non_exhaustive]
 pub enum ReadPacketError<E> {
     /// A SPI error was encountered
     SpiError(E),
     /// An error was encountered with the recieved packet
     PacketError(PacketError),
+    /// No packet was received within the allotted time
+    Timeout,
 }
 
 /// A type that represents the errors that were encountered with a
@@ -36,3 +40,329 @@ impl From<Mode> for PacketError {
         }
     }
 }
+
+/// An error that can result from sending or receiving data over a
+/// [`ReliableLink`](crate::reliable::ReliableLink)
+#[derive(Format, PartialEq, Debug, Clone)]
+pub enum LinkError<E> {
+    /// A SPI error was encountered
+    SpiError(E),
+    /// The payload does not fit into a single frame
+    PayloadTooLarge,
+    /// The provided buffer is too small to hold the received payload
+    BufferTooSmall,
+    /// The frame was not acknowledged by the peer after all retries were exhausted
+    NoAck,
+    /// No frame was received within the allotted time
+    Timeout,
+}
+
+impl<E> From<E> for LinkError<E> {
+    fn from(value: E) -> Self {
+        Self::SpiError(value)
+    }
+}
+
+/// An error that can result from communicating over a star network through a
+/// [`Hub`](crate::network::Hub) or [`Node`](crate::network::Node)
+#[derive(Format, PartialEq, Debug, Clone)]
+pub enum NetworkError<E> {
+    /// A SPI error was encountered
+    SpiError(E),
+    /// The payload does not fit into a single frame
+    PayloadTooLarge,
+    /// The provided buffer is too small to hold the received payload
+    BufferTooSmall,
+    /// The address is reserved and can not be used for a node
+    InvalidAddress,
+    /// The hub has no room left to register another node
+    NodeTableFull,
+    /// No frame was received within the allotted time
+    Timeout,
+}
+
+impl<E> From<E> for NetworkError<E> {
+    fn from(value: E) -> Self {
+        Self::SpiError(value)
+    }
+}
+
+/// An error that can result from communicating within a TDMA frame through a
+/// [`TdmaMaster`](crate::tdma::TdmaMaster) or [`TdmaNode`](crate::tdma::TdmaNode)
+#[derive(Format, PartialEq, Debug, Clone)]
+pub enum TdmaError<E> {
+    /// A SPI error was encountered
+    SpiError(E),
+    /// An error was encountered with the recieved packet
+    PacketError(PacketError),
+    /// The node has not yet received a beacon to synchronise to
+    NotSynced,
+    /// The slot does not exist in the configured frame, or is the beacon slot
+    InvalidSlot,
+    /// The [`TdmaConfig`](crate::tdma::TdmaConfig) has no slots, or slots shorter than a
+    /// microsecond
+    InvalidConfig,
+    /// No packet was received within the allotted time
+    Timeout,
+}
+
+impl<E> From<E> for TdmaError<E> {
+    fn from(value: E) -> Self {
+        Self::SpiError(value)
+    }
+}
+
+impl<E> From<ReadPacketError<E>> for TdmaError<E> {
+    fn from(value: ReadPacketError<E>) -> Self {
+        match value {
+            ReadPacketError::SpiError(e) => Self::SpiError(e),
+            ReadPacketError::PacketError(e) => Self::PacketError(e),
+            ReadPacketError::Timeout => Self::Timeout,
+        }
+    }
+}
+
+/// An error that can result from binding with a peer through a
+/// [`Binder`](crate::bind::Binder)
+#[derive(Format, PartialEq, Debug, Clone)]
+pub enum BindError<E, S> {
+    /// A SPI error was encountered
+    SpiError(E),
+    /// The [`BindingStore`](crate::bind::BindingStore) failed to persist the binding
+    StoreError(S),
+    /// The peer did not complete the handshake within the allotted time
+    Timeout,
+}
+
+impl<E, S> From<E> for BindError<E, S> {
+    fn from(value: E) -> Self {
+        Self::SpiError(value)
+    }
+}
+
+/// An error that can result from applying a high level radio configuration
+#[derive(Format, PartialEq, Debug, Clone)]
+pub enum ConfigError<E> {
+    /// A SPI error was encountered
+    SpiError(E),
+    /// The requested data rate cannot be produced from the configured system clock
+    UnsupportedDataRate,
+    /// The frequency deviation cannot be produced from the configured PLL comparison
+    /// frequency
+    UnsupportedDeviation,
+    /// The frequency deviation does not fit within the RX bandwidth
+    DeviationExceedsBandwidth,
+    /// The frequency deviation is too small to be demodulated at the configured data rate
+    ModulationIndexTooLow,
+    /// A moving average was requested alongside the Gaussian filter
+    ConflictingModulation,
+    /// A register did not read back the value written to it, with write verification
+    /// enabled through [`A7105::set_write_verify`](crate::A7105::set_write_verify)
+    VerifyError(VerifyError),
+}
+
+impl<E> From<E> for ConfigError<E> {
+    fn from(value: E) -> Self {
+        Self::SpiError(value)
+    }
+}
+
+impl<E> From<WriteError<E>> for ConfigError<E> {
+    fn from(value: WriteError<E>) -> Self {
+        match value {
+            WriteError::SpiError(e) => Self::SpiError(e),
+            WriteError::VerifyError(e) => Self::VerifyError(e),
+        }
+    }
+}
+
+impl ConfigError<Infallible> {
+    /// Converts an error raised without any SPI communication into one for any SPI error
+    pub(crate) fn widen<E>(self) -> ConfigError<E> {
+        match self {
+            Self::SpiError(e) => match e {},
+            Self::UnsupportedDataRate => ConfigError::UnsupportedDataRate,
+            Self::UnsupportedDeviation => ConfigError::UnsupportedDeviation,
+            Self::DeviationExceedsBandwidth => ConfigError::DeviationExceedsBandwidth,
+            Self::ModulationIndexTooLow => ConfigError::ModulationIndexTooLow,
+            Self::ConflictingModulation => ConfigError::ConflictingModulation,
+            Self::VerifyError(e) => ConfigError::VerifyError(e),
+        }
+    }
+}
+
+/// An error that can result from duty-cycled receiving through a
+/// [`WakeOnRx`](crate::wake_on_rx::WakeOnRx)
+#[derive(Format, PartialEq, Debug, Clone)]
+pub enum WakeError<E, P> {
+    /// A SPI error was encountered
+    SpiError(E),
+    /// An error was encountered reading the WTR pin
+    PinError(P),
+}
+
+impl<E, P> From<E> for WakeError<E, P> {
+    fn from(value: E) -> Self {
+        Self::SpiError(value)
+    }
+}
+
+/// An error that can result from a transaction on a
+/// [`ThreeWireSpi`](crate::three_wire::ThreeWireSpi)
+#[derive(Format, PartialEq, Debug, Clone)]
+pub enum ThreeWireError<E> {
+    /// An error was encountered driving or reading one of the pins
+    PinError(E),
+    /// A full duplex transfer was requested, which the shared SDIO line can not perform
+    FullDuplex,
+}
+
+impl<E> From<E> for ThreeWireError<E> {
+    fn from(value: E) -> Self {
+        Self::PinError(value)
+    }
+}
+
+impl<E: core::fmt::Debug> embedded_hal::spi::Error for ThreeWireError<E> {
+    fn kind(&self) -> embedded_hal::spi::ErrorKind {
+        embedded_hal::spi::ErrorKind::Other
+    }
+}
+
+/// An error that can result from a verified register write through
+/// [`A7105::write_reg_verified`](crate::A7105::write_reg_verified)
+#[derive(Format, PartialEq, Debug, Clone)]
+pub enum WriteError<E> {
+    /// A SPI error was encountered
+    SpiError(E),
+    /// The register did not read back the value that was written
+    VerifyError(VerifyError),
+}
+
+impl<E> From<E> for WriteError<E> {
+    fn from(value: E) -> Self {
+        Self::SpiError(value)
+    }
+}
+
+/// A register that did not read back the value written to it, even after retrying
+///
+/// Multi-byte registers are stored with the first byte transferred in the least
+/// significant byte. Bits outside of the register's
+/// [`readback_mask`](crate::registers::WritableRegister::readback_mask) are cleared.
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct VerifyError {
+    /// The address of the register
+    pub reg: u8,
+    /// The value that was written
+    pub wrote: u32,
+    /// The value that was read back on the last attempt
+    pub read: u32,
+}
+
+/// An error that can result from coordinating several radios through a
+/// [`RadioManager`](crate::manager::RadioManager)
+#[derive(Format, PartialEq, Debug, Clone)]
+pub enum ManagerError<E> {
+    /// A SPI error was encountered
+    SpiError(E),
+    /// An error was encountered with the recieved packet
+    PacketError(PacketError),
+    /// The radio index does not exist, or the same radio was given for two roles
+    InvalidRadio,
+    /// The radios are tuned too close together to be used at the same time
+    ChannelCollision,
+    /// The radios are not all tuned to the same channel
+    ChannelMismatch,
+    /// No packet was received within the allotted time
+    Timeout,
+}
+
+impl<E> From<E> for ManagerError<E> {
+    fn from(value: E) -> Self {
+        Self::SpiError(value)
+    }
+}
+
+impl<E> From<ReadPacketError<E>> for ManagerError<E> {
+    fn from(value: ReadPacketError<E>) -> Self {
+        match value {
+            ReadPacketError::SpiError(e) => Self::SpiError(e),
+            ReadPacketError::PacketError(e) => Self::PacketError(e),
+            ReadPacketError::Timeout => Self::Timeout,
+        }
+    }
+}
+
+/// An error that can result from reading or writing an
+/// [`A7105Stream`](crate::stream::A7105Stream)
+#[cfg(feature = "stream")]
+#[derive(Format, PartialEq, Debug, Clone)]
+pub enum StreamError<E> {
+    /// A SPI error was encountered
+    SpiError(E),
+}
+
+#[cfg(feature = "stream")]
+impl<E> From<E> for StreamError<E> {
+    fn from(value: E) -> Self {
+        Self::SpiError(value)
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<E: core::fmt::Debug> embedded_io::Error for StreamError<E> {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::Other
+    }
+}
+
+/// An error that can result from sending or receiving data over a
+/// [`SecureLink`](crate::secure::SecureLink)
+#[cfg(feature = "secure")]
+#[derive(Format, PartialEq, Debug, Clone)]
+pub enum SecureError<E> {
+    /// A SPI error was encountered
+    SpiError(E),
+    /// An error was encountered with the recieved packet
+    PacketError(PacketError),
+    /// The payload does not fit into a single frame
+    PayloadTooLarge,
+    /// The provided buffer is too small to hold the received payload
+    BufferTooSmall,
+    /// No frame was received within the allotted time
+    Timeout,
+    /// The frame was not sealed with the key it claims, or was modified in transit
+    AuthenticationFailed,
+    /// The frame has already been received, or is too old to tell
+    Replay,
+    /// The frame was sealed with a key that is neither the current nor the previous key
+    UnknownKey,
+    /// The frame counter is exhausted and the key must be rotated before sending again
+    CounterExhausted,
+    /// The frame header is invalid
+    Malformed,
+    /// The frame is authentic, but a replay window is already tracked for every peer the
+    /// [`SecureLink`](crate::secure::SecureLink) has room for
+    TooManyPeers,
+    /// The cipher failed to seal the frame
+    EncryptionFailed,
+}
+
+#[cfg(feature = "secure")]
+impl<E> From<E> for SecureError<E> {
+    fn from(value: E) -> Self {
+        Self::SpiError(value)
+    }
+}
+
+#[cfg(feature = "secure")]
+impl<E> From<ReadPacketError<E>> for SecureError<E> {
+    fn from(value: ReadPacketError<E>) -> Self {
+        match value {
+            ReadPacketError::SpiError(e) => Self::SpiError(e),
+            ReadPacketError::PacketError(e) => Self::PacketError(e),
+            ReadPacketError::Timeout => Self::Timeout,
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! An interrupt driven driver for RTIC and other poll free applications
+//!
+//! [`IrqRadio::start_rx`] and [`IrqRadio::start_tx`] only issue the SPI transactions needed
+//! to start a reception or transmission and return immediately. The A7105 then signals the
+//! end of the transfer through a GIO pin configured for
+//! [`GpioPinFunction::Wtr`](crate::registers::GpioPinFunction::Wtr), which is high while a
+//! transfer is in progress, and the falling edge interrupt calls
+//! [`IrqRadio::on_gpio_interrupt`] to advance the state machine: the received packet is
+//! read from the FIFO, its CRC and FEC checks inspected, and the receiver re-armed.
+//!
+//! With RTIC the [`IrqRadio`] is a shared resource, locked by the tasks starting transfers
+//! and by the hardware task bound to the GIO1 interrupt. Hardware tasks can not await, so
+//! the example below requires the `blocking` feature, enabled with
+//! `--no-default-features --features blocking`:
+//!
+//! ```ignore
+//! use a7105::irq::{IrqEvent, IrqRadio};
+//! use a7105::prelude::*;
+//!
+//! #[init]
+//! fn init(cx: init::Context) -> (Shared, Local) {
+//!     # let (a7105_spi_peripheral, mut gio1) = unimplemented!();
+//!     let mut radio = A7105::new(a7105_spi_peripheral);
+//!     // GIO1 goes low once a packet has been sent or received
+//!     radio
+//!         .write_reg(registers::Gpio1PinControl {
+//!             pin_function: registers::GpioPinFunction::Wtr,
+//!             ..Default::default()
+//!         })
+//!         .unwrap();
+//!     gio1.trigger_on_edge(&mut cx.device.EXTI, Edge::Falling);
+//!     gio1.enable_interrupt(&mut cx.device.EXTI);
+//!
+//!     let mut radio: IrqRadio<_, 16> = IrqRadio::new(radio);
+//!     radio.start_rx().unwrap();
+//!     (Shared { radio }, Local { gio1 })
+//! }
+//!
+//! #[task(binds = EXTI0, shared = [radio], local = [gio1])]
+//! fn gio1_interrupt(mut cx: gio1_interrupt::Context) {
+//!     cx.local.gio1.clear_interrupt_pending_bit();
+//!     match cx.shared.radio.lock(|radio| radio.on_gpio_interrupt()) {
+//!         Ok(IrqEvent::Received(packet)) => defmt::info!("received {}", packet),
+//!         Ok(IrqEvent::PacketError(e)) => defmt::warn!("corrupt packet {}", e),
+//!         Ok(_) => {}
+//!         Err(_) => defmt::error!("SPI error"),
+//!     }
+//! }
+//! ```
+
+use crate::{commands::Mode, registers, PacketError, ReadPacketError, A7105};
+use defmt::Format;
+
+#[cfg(feature = "blocking")]
+use embedded_hal::spi::SpiDevice;
+#[cfg(feature = "async")]
+use embedded_hal_async::spi::SpiDevice;
+
+/// The transfer an [`IrqRadio`] is waiting on
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub enum IrqState {
+    /// No transfer is in progress, so interrupts are ignored
+    Idle,
+    /// Waiting for a packet to be received
+    Rx,
+    /// Waiting for a packet to finish transmitting
+    Tx,
+}
+
+/// The outcome of handling a GIO interrupt
+#[derive(Format, PartialEq, Debug, Clone)]
+pub enum IrqEvent<const N: usize> {
+    /// No transfer completed, either because none was in progress or the A7105 is still busy
+    None,
+    /// The packet started through [`IrqRadio::start_tx`] has been transmitted
+    Transmitted,
+    /// A valid packet has been received
+    Received([u8; N]),
+    /// A packet was received but failed its CRC or FEC checks
+    PacketError(PacketError),
+}
+
+/// A non-blocking state machine driving the A7105 from its GIO interrupt, receiving packets
+/// of `N` bytes
+///
+/// Refer to the [module level documentation](self) for an overview.
+pub struct IrqRadio<SPI, const N: usize> {
+    radio: A7105<SPI>,
+    state: IrqState,
+    auto_rearm: bool,
+}
+
+impl<SPI, const N: usize> IrqRadio<SPI, N> {
+    /// Constructs a new, idle [`IrqRadio`]
+    ///
+    /// The A7105 must already be configured, including the GIO pin used to signal the end of
+    /// a transfer. The receiver is re-armed after every transfer by default.
+    pub fn new(radio: A7105<SPI>) -> Self {
+        Self {
+            radio,
+            state: IrqState::Idle,
+            auto_rearm: true,
+        }
+    }
+
+    /// Destroys this [`IrqRadio`], returning the radio
+    pub fn release(self) -> A7105<SPI> {
+        self.radio
+    }
+
+    /// Returns the radio, for configuring it while no transfer is in progress
+    pub fn radio(&mut self) -> &mut A7105<SPI> {
+        &mut self.radio
+    }
+
+    /// Returns the transfer currently being waited on
+    pub fn state(&self) -> IrqState {
+        self.state
+    }
+
+    /// Sets whether the A7105 is placed back in [`Mode::Rx`] once a transfer completes,
+    /// rather than being left idle
+    pub fn set_auto_rearm(&mut self, auto_rearm: bool) {
+        self.auto_rearm = auto_rearm;
+    }
+}
+
+impl<SPI: SpiDevice, const N: usize> IrqRadio<SPI, N> {
+    /// Places the A7105 in [`Mode::Rx`], returning without waiting for a packet
+    #[maybe_async::maybe_async]
+    pub async fn start_rx(&mut self) -> Result<(), SPI::Error> {
+        self.state = IrqState::Idle;
+        self.radio.set_mode(Mode::Rx).await?;
+        self.state = IrqState::Rx;
+        Ok(())
+    }
+
+    /// Writes a packet to the TX FIFO and places the A7105 in [`Mode::Tx`], returning
+    /// without waiting for the transmission to complete
+    ///
+    /// Any reception in progress is abandoned.
+    #[maybe_async::maybe_async]
+    pub async fn start_tx(&mut self, buf: &[u8]) -> Result<(), SPI::Error> {
+        self.state = IrqState::Idle;
+        self.radio.set_mode(Mode::Standby).await?;
+        self.radio.tx(buf).await?;
+        self.radio.set_mode(Mode::Tx).await?;
+        self.state = IrqState::Tx;
+        Ok(())
+    }
+
+    /// Abandons any transfer in progress and places the A7105 in [`Mode::Standby`]
+    #[maybe_async::maybe_async]
+    pub async fn cancel(&mut self) -> Result<(), SPI::Error> {
+        self.state = IrqState::Idle;
+        self.radio.set_mode(Mode::Standby).await
+    }
+
+    /// Advances the state machine, to be called from the GIO interrupt handler
+    ///
+    /// Interrupts received while idle or while the A7105 is still busy are ignored and
+    /// return [`IrqEvent::None`]. Otherwise the completed transfer is reported and, unless
+    /// disabled through [`IrqRadio::set_auto_rearm`], the A7105 is placed back in
+    /// [`Mode::Rx`].
+    #[maybe_async::maybe_async]
+    pub async fn on_gpio_interrupt(&mut self) -> Result<IrqEvent<N>, SPI::Error> {
+        if self.state == IrqState::Idle {
+            return Ok(IrqEvent::None);
+        }
+        let mode: registers::Mode = self.radio.read_reg().await?;
+        if mode.trx_enabled {
+            return Ok(IrqEvent::None);
+        }
+
+        let event = match self.state {
+            IrqState::Idle => IrqEvent::None,
+            IrqState::Tx => IrqEvent::Transmitted,
+            IrqState::Rx => {
+                let mut packet = [0; N];
+                match self.radio.rx(&mut packet).await {
+                    Ok(()) => IrqEvent::Received(packet),
+                    Err(ReadPacketError::SpiError(e)) => return Err(e),
+                    Err(ReadPacketError::PacketError(e)) => IrqEvent::PacketError(e),
+                    Err(ReadPacketError::Timeout) => IrqEvent::None,
+                }
+            }
+        };
+
+        self.state = IrqState::Idle;
+        if self.auto_rearm {
+            self.start_rx().await?;
+        }
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::{run, MockSpi};
+
+    fn irq_radio() -> (IrqRadio<MockSpi, 3>, MockSpi) {
+        let spi = MockSpi::new();
+        (IrqRadio::new(A7105::new(spi.clone())), spi)
+    }
+
+    #[test]
+    fn test_ignored_interrupts() {
+        let (mut radio, spi) = irq_radio();
+        assert_eq!(run!(radio.on_gpio_interrupt()), Ok(IrqEvent::None));
+        assert!(spi.sim().transactions.is_empty());
+
+        // A spurious interrupt while the packet is still arriving
+        spi.sim().airtime_polls = 2;
+        spi.sim().push(&[1, 2, 3]);
+        run!(radio.start_rx()).unwrap();
+        assert_eq!(run!(radio.on_gpio_interrupt()), Ok(IrqEvent::None));
+        assert_eq!(radio.state(), IrqState::Rx);
+        assert!(spi.sim().in_rx());
+    }
+
+    #[test]
+    fn test_received() {
+        let (mut radio, spi) = irq_radio();
+        spi.sim().push(&[1, 2, 3]);
+        run!(radio.start_rx()).unwrap();
+        assert_eq!(
+            run!(radio.on_gpio_interrupt()),
+            Ok(IrqEvent::Received([1, 2, 3]))
+        );
+        assert_eq!(radio.state(), IrqState::Rx);
+        assert!(spi.sim().in_rx());
+
+        spi.sim().push_corrupt(&[1, 2, 3]);
+        assert_eq!(
+            run!(radio.on_gpio_interrupt()),
+            Ok(IrqEvent::PacketError(PacketError {
+                fec_failed: false,
+                crc_failed: true,
+            }))
+        );
+        assert_eq!(radio.state(), IrqState::Rx);
+    }
+
+    #[test]
+    fn test_auto_rearm() {
+        let (mut radio, spi) = irq_radio();
+        run!(radio.start_tx(&[4, 5, 6])).unwrap();
+        assert_eq!(radio.state(), IrqState::Tx);
+        assert_eq!(run!(radio.on_gpio_interrupt()), Ok(IrqEvent::Transmitted));
+        assert_eq!(spi.sim().sent[0][..], [4, 5, 6]);
+        assert_eq!(radio.state(), IrqState::Rx);
+        assert!(spi.sim().in_rx());
+
+        radio.set_auto_rearm(false);
+        spi.sim().push(&[1, 2, 3]);
+        assert_eq!(
+            run!(radio.on_gpio_interrupt()),
+            Ok(IrqEvent::Received([1, 2, 3]))
+        );
+        assert_eq!(radio.state(), IrqState::Idle);
+        assert!(!spi.sim().in_rx());
+        assert_eq!(run!(radio.on_gpio_interrupt()), Ok(IrqEvent::None));
+    }
+}
@@ -1,4 +1,7 @@
 #![no_std]
+#![cfg_attr(nightly, allow(stable_features, unknown_lints))]
+#![cfg_attr(nightly, feature(async_fn_in_trait))]
+#![allow(async_fn_in_trait)]
 
 //! `a7105` is a Rust crate that provides a high-level interface for interacting
 //! with the A7105 2.4GHz FSK/GFSK Transceiver, built on top of
@@ -24,25 +27,63 @@ pub use error::*;
 use registers::{ReadableRegister, WritableRegister};
 
 #[cfg(feature = "blocking")]
-use embedded_hal::spi::{Operation, SpiDevice};
+use embedded_hal::{
+    delay::DelayNs,
+    spi::{Operation, SpiDevice},
+};
 #[cfg(feature = "async")]
-use embedded_hal_async::spi::{Operation, SpiDevice};
+use embedded_hal_async::{
+    delay::DelayNs,
+    spi::{Operation, SpiDevice},
+};
 
+pub mod agc;
+pub mod battery;
+pub mod bind;
 pub mod commands;
+#[cfg(feature = "embassy")]
+pub mod embassy;
 mod error;
+pub mod irq;
+pub mod manager;
+#[cfg(test)]
+mod mock;
+pub mod modulation;
+pub mod network;
+pub mod power;
 pub mod prelude;
+#[cfg(all(feature = "radio", feature = "blocking"))]
+pub mod radio;
 pub mod registers;
+pub mod reliable;
+#[cfg(feature = "secure")]
+pub mod secure;
+pub mod shadow;
+pub mod snapshot;
+#[cfg(feature = "stream")]
+pub mod stream;
+pub mod tdma;
+pub mod three_wire;
+pub mod time;
+pub mod wake_on_rx;
 
 /// The `A7105` is the primary type for interfacing with the
 /// radio hardware.
 pub struct A7105<SPI> {
     spi: SPI,
+    tx_power: registers::TxPower,
+    agc: Option<agc::Agc>,
+    verify_retries: Option<u8>,
+    verify_failure: Option<VerifyError>,
+    shadow: Option<shadow::ShadowRegisters>,
 }
 
 impl<SPI> A7105<SPI> {
     const RX_BUFFER_ID: u8 = 0x05;
     const TX_BUFFER_ID: u8 = 0x05;
     const READ_FLAG: u8 = 0x40;
+    const POLL_INTERVAL_US: u32 = 50;
+    const BATTERY_DETECT_US: u32 = 5;
 
     /// Constructs a new instance of a [`A7105`] from the provided [`SpiDevice`]
     ///
@@ -51,7 +92,14 @@ impl<SPI> A7105<SPI> {
     /// peripheral has been previously configured, and that all radio configuration
     /// will be explicitly handled through the returned [`A7105`] instance.
     pub const fn new(spi: SPI) -> Self {
-        Self { spi }
+        Self {
+            spi,
+            tx_power: registers::TxPower::RESET,
+            agc: None,
+            verify_retries: None,
+            verify_failure: None,
+            shadow: None,
+        }
     }
 
     /// Destroys this instance of the [`A7105`], returning the inner [`SpiDevice`]
@@ -61,6 +109,56 @@ impl<SPI> A7105<SPI> {
     pub fn destroy(self) -> SPI {
         self.spi
     }
+
+    /// Enables or disables verification of every register write made through
+    /// [`A7105::write_reg`]
+    ///
+    /// When enabled, every register that can be read back is read after being written and
+    /// rewritten up to `retries` times until it holds the written value. A register that
+    /// still does not match is returned as [`ConfigError::VerifyError`] by the configuration
+    /// methods such as [`A7105::set_data_rate`] and [`A7105::set_modulation`]. As
+    /// [`A7105::write_reg`] can only report SPI errors, it instead records the mismatch to be
+    /// retrieved through [`A7105::take_verify_error`]. Use [`A7105::write_reg_verified`] to
+    /// have mismatches returned directly.
+    pub fn set_write_verify(&mut self, retries: Option<u8>) {
+        self.verify_retries = retries;
+    }
+
+    /// Returns and clears the first verification failure recorded by [`A7105::write_reg`]
+    /// since this was last called
+    ///
+    /// Later failures are discarded until the recorded one is taken, as they are often a
+    /// consequence of the first.
+    pub fn take_verify_error(&mut self) -> Option<VerifyError> {
+        self.verify_failure.take()
+    }
+
+    /// Starts caching every register written through [`A7105::write_reg`]
+    ///
+    /// Refer to the [`shadow`] module for an overview. Registers written before the cache
+    /// was enabled are not known to it.
+    pub fn enable_shadow(&mut self) {
+        self.shadow.get_or_insert_with(shadow::ShadowRegisters::new);
+    }
+
+    /// Stops caching register writes, discarding the cached values
+    pub fn disable_shadow(&mut self) {
+        self.shadow = None;
+    }
+
+    /// Returns the cached register values, or `None` if the cache is disabled
+    pub fn shadow(&self) -> Option<&shadow::ShadowRegisters> {
+        self.shadow.as_ref()
+    }
+
+    /// Returns the transmit power level last configured through [`A7105::set_tx_power`]
+    ///
+    /// The [`TxTest`](registers::TxTest) register is write only, so this reports the reset
+    /// level until a level has been set, and does not observe writes made directly through
+    /// [`A7105::write_reg`].
+    pub fn tx_power(&self) -> registers::TxPower {
+        self.tx_power
+    }
 }
 
 impl<SPI: SpiDevice> A7105<SPI> {
@@ -83,12 +181,7 @@ impl<SPI: SpiDevice> A7105<SPI> {
         &mut self,
     ) -> Result<R, SPI::Error> {
         let mut buf = [0u8; N];
-        self.spi
-            .transaction(&mut [
-                Operation::Write(&[R::id() | Self::READ_FLAG]),
-                Operation::Read(&mut buf),
-            ])
-            .await?;
+        self.read_bytes(R::id(), &mut buf).await?;
         Ok(R::from_slice(buf))
     }
 
@@ -111,16 +204,258 @@ impl<SPI: SpiDevice> A7105<SPI> {
     /// // provided type.
     /// radio.write_reg(id_data).await.unwrap();
     /// ````
+    ///
+    /// If write verification has been enabled through [`A7105::set_write_verify`], the
+    /// register is read back after being written, and a mismatch is recorded for
+    /// [`A7105::take_verify_error`]. If the [`shadow`] cache has been enabled through
+    /// [`A7105::enable_shadow`], the written value is cached.
+    #[maybe_async::maybe_async]
+    pub async fn write_reg<const N: usize, R: WritableRegister<N> + Copy>(
+        &mut self,
+        reg: R,
+    ) -> Result<(), SPI::Error> {
+        match self.write_reg_checked(reg).await {
+            Ok(()) => Ok(()),
+            Err(WriteError::SpiError(e)) => Err(e),
+            Err(WriteError::VerifyError(e)) => {
+                self.verify_failure.get_or_insert(e);
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes a register as [`A7105::write_reg`] does, returning any verification failure
+    /// instead of recording it
+    #[maybe_async::maybe_async]
+    async fn write_reg_checked<const N: usize, R: WritableRegister<N> + Copy>(
+        &mut self,
+        reg: R,
+    ) -> Result<(), WriteError<SPI::Error>> {
+        let bytes = reg.into_slice();
+        let mut failure = None;
+        match (self.verify_retries, R::readback_mask()) {
+            (Some(retries), Some(mask)) => {
+                match self
+                    .write_bytes_verified(R::id(), bytes, mask, retries)
+                    .await
+                {
+                    Err(WriteError::VerifyError(e)) => failure = Some(e),
+                    result => result?,
+                }
+            }
+            _ => self.write_bytes(R::id(), &bytes).await?,
+        }
+
+        // The intended value is cached even if it did not read back, so that a resync
+        // retries it
+        if let Some(shadow) = &mut self.shadow {
+            shadow.set(reg);
+        }
+        failure.map_or(Ok(()), |e| Err(WriteError::VerifyError(e)))
+    }
+
+    /// Changes the fields of a register in place, without reading it from the A7105
+    ///
+    /// The closure is given the value held by the [`shadow`] cache, or the default value
+    /// of the register if the cache is disabled, the register has not been written yet or
+    /// the A7105 has been reset since the cache was last written back through
+    /// [`A7105::resync`]. The modified value is then written through [`A7105::write_reg`].
+    ///
+    /// ```ignore
+    /// use a7105::prelude::*;
+    ///
+    /// # let a7105_spi_peripheral = unimplemented!();
+    /// let mut radio = A7105::new(a7105_spi_peripheral);
+    /// radio.enable_shadow();
+    ///
+    /// radio
+    ///     .modify_reg(|tx1: &mut registers::Tx1| tx1.filter_enable = true)
+    ///     .await
+    ///     .unwrap();
+    /// ````
     #[maybe_async::maybe_async]
-    pub async fn write_reg<R: WritableRegister>(&mut self, reg: R) -> Result<(), SPI::Error> {
+    pub async fn modify_reg<const N: usize, R: WritableRegister<N> + Copy + Default>(
+        &mut self,
+        f: impl FnOnce(&mut R),
+    ) -> Result<(), SPI::Error> {
+        let mut reg = self.cached_or_default();
+        f(&mut reg);
+        self.write_reg(reg).await
+    }
+
+    /// Returns the value of a register held by the [`shadow`] cache, or its default value
+    /// if the cached value is missing or stale
+    fn cached_or_default<const N: usize, R: WritableRegister<N> + Copy + Default>(&self) -> R {
+        self.shadow
+            .as_ref()
+            .filter(|shadow| !shadow.is_stale())
+            .and_then(|shadow| shadow.get())
+            .unwrap_or_default()
+    }
+
+    /// Rewrites every register held by the [`shadow`] cache, in address order
+    ///
+    /// This restores the configuration after a reset or brownout. Bits that start an
+    /// action, such as [`ModeControl::adc_measurement_enabled`](registers::ModeControl),
+    /// are cleared before being written, and calibrations must be run again afterwards.
+    /// The cached transmit power is restored along with [`TxTest`](registers::TxTest),
+    /// while the software AGC remains disabled.
+    #[maybe_async::maybe_async]
+    pub async fn resync(&mut self) -> Result<(), SPI::Error> {
+        let Some(shadow) = self.shadow else {
+            return Ok(());
+        };
+
+        let mode_control = shadow.mode_control.map(|reg| registers::ModeControl {
+            adc_measurement_enabled: false,
+            ..reg
+        });
+        let battery_detect_config =
+            shadow
+                .battery_detect_config
+                .map(|reg| registers::BatteryDetectConfig {
+                    detect_enabled: false,
+                    ..reg
+                });
+
+        self.rewrite_reg(mode_control).await?;
+        self.rewrite_reg(shadow.fifo1).await?;
+        self.rewrite_reg(shadow.fifo2).await?;
+        self.rewrite_reg(shadow.id_data).await?;
+        self.rewrite_reg(shadow.rc_osc3).await?;
+        self.rewrite_reg(shadow.cko_pin_control).await?;
+        self.rewrite_reg(shadow.gpio1_pin_control).await?;
+        self.rewrite_reg(shadow.gpio2_pin_control).await?;
+        self.rewrite_reg(shadow.clock).await?;
+        self.rewrite_reg(shadow.data_rate).await?;
+        self.rewrite_reg(shadow.pll1).await?;
+        self.rewrite_reg(shadow.pll2).await?;
+        self.rewrite_reg(shadow.pll3).await?;
+        self.rewrite_reg(shadow.pll4).await?;
+        self.rewrite_reg(shadow.pll5).await?;
+        self.rewrite_reg(shadow.tx1).await?;
+        self.rewrite_reg(shadow.tx2).await?;
+        self.rewrite_reg(shadow.delay1).await?;
+        self.rewrite_reg(shadow.delay2).await?;
+        self.rewrite_reg(shadow.rx).await?;
+        self.rewrite_reg(shadow.rx_gain1).await?;
+        self.rewrite_reg(shadow.rx_gain2).await?;
+        self.rewrite_reg(shadow.rx_gain3).await?;
+        self.rewrite_reg(shadow.rx_gain4).await?;
+        self.rewrite_reg(shadow.rssi_carrier_detect_threshold)
+            .await?;
+        self.rewrite_reg(shadow.adc_control).await?;
+        self.rewrite_reg(shadow.code1).await?;
+        self.rewrite_reg(shadow.code2).await?;
+        self.rewrite_reg(shadow.code3).await?;
+        self.rewrite_reg(shadow.if_calibration1_config).await?;
+        self.rewrite_reg(shadow.vco_current_calibration).await?;
+        self.rewrite_reg(shadow.vco_single_band_calibration1)
+            .await?;
+        self.rewrite_reg(shadow.vco_single_band_calibration2)
+            .await?;
+        self.rewrite_reg(battery_detect_config).await?;
+        self.rewrite_reg(shadow.tx_test).await?;
+
+        if let Some(level) = shadow
+            .tx_test
+            .and_then(|reg| registers::TxPower::from_tx_test(&reg))
+        {
+            self.tx_power = level;
+        }
+        if let Some(shadow) = &mut self.shadow {
+            shadow.stale = false;
+        }
+        Ok(())
+    }
+
+    #[maybe_async::maybe_async]
+    async fn rewrite_reg<const N: usize, R: WritableRegister<N> + Copy>(
+        &mut self,
+        reg: Option<R>,
+    ) -> Result<(), SPI::Error> {
+        match reg {
+            Some(reg) => self.write_reg(reg).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Writes a value to a register on the A7105, then reads it back to confirm it was
+    /// written correctly
+    ///
+    /// The register is rewritten up to `retries` times until it reads back the written
+    /// value, ignoring bits that are read only or clear automatically, after which
+    /// [`WriteError::VerifyError`] is returned.
+    #[maybe_async::maybe_async]
+    pub async fn write_reg_verified<
+        const N: usize,
+        R: ReadableRegister<N> + WritableRegister<N>,
+    >(
+        &mut self,
+        reg: R,
+        retries: u8,
+    ) -> Result<(), WriteError<SPI::Error>> {
+        let mask = R::readback_mask().unwrap_or([0xFF; N]);
+        self.write_bytes_verified(R::id(), reg.into_slice(), mask, retries)
+            .await
+    }
+
+    #[maybe_async::maybe_async]
+    async fn read_bytes(&mut self, id: u8, buf: &mut [u8]) -> Result<(), SPI::Error> {
         self.spi
             .transaction(&mut [
-                Operation::Write(&[R::id()]),
-                Operation::Write(&reg.into_slice()),
+                Operation::Write(&[id | Self::READ_FLAG]),
+                Operation::Read(buf),
             ])
             .await
     }
 
+    #[maybe_async::maybe_async]
+    async fn write_bytes(&mut self, id: u8, bytes: &[u8]) -> Result<(), SPI::Error> {
+        self.spi
+            .transaction(&mut [Operation::Write(&[id]), Operation::Write(bytes)])
+            .await
+    }
+
+    #[maybe_async::maybe_async]
+    async fn write_bytes_verified<const N: usize>(
+        &mut self,
+        id: u8,
+        bytes: [u8; N],
+        mask: [u8; N],
+        retries: u8,
+    ) -> Result<(), WriteError<SPI::Error>> {
+        let masked = |bytes: [u8; N]| {
+            bytes
+                .iter()
+                .zip(mask)
+                .enumerate()
+                .fold(0, |value, (i, (byte, mask))| {
+                    value | u32::from(byte & mask) << (8 * i)
+                })
+        };
+
+        let mut attempt = 0;
+        loop {
+            self.write_bytes(id, &bytes).await?;
+            let mut read = [0; N];
+            self.read_bytes(id, &mut read).await?;
+
+            let (wrote, read) = (masked(bytes), masked(read));
+            if wrote == read {
+                return Ok(());
+            }
+            if attempt == retries {
+                return Err(WriteError::VerifyError(VerifyError {
+                    reg: id,
+                    wrote,
+                    read,
+                }));
+            }
+            attempt += 1;
+        }
+    }
+
     /// Sets the A7105 into the specified [`Mode`]
     ///
     /// This method is used to change the operating mode of the A7105 chip. For
@@ -139,7 +474,14 @@ impl<SPI: SpiDevice> A7105<SPI> {
     #[maybe_async::maybe_async]
     pub async fn command(&mut self, command: Command) -> Result<(), SPI::Error> {
         let buf: &[u8] = match command {
-            Command::Reset => &[0x00, 0x00],
+            Command::Reset => {
+                self.tx_power = registers::TxPower::RESET;
+                self.agc = None;
+                if let Some(shadow) = &mut self.shadow {
+                    shadow.stale = true;
+                }
+                &[0x00, 0x00]
+            }
             Command::ResetFifoReadPointer => &[0b1111_0000],
             Command::ResetFifoWritePointer => &[0b1110_0000],
         };
@@ -183,4 +525,359 @@ impl<SPI: SpiDevice> A7105<SPI> {
             ])
             .await
     }
+
+    /// Returns `true` while the A7105 is still busy transmitting or receiving a packet
+    ///
+    /// In FIFO mode the A7105 automatically leaves [`Mode::Tx`]/[`Mode::Rx`] once a
+    /// complete packet has been sent or received, which is reported through the
+    /// `trx_enabled` flag of the [`Mode`](registers::Mode) register.
+    #[maybe_async::maybe_async]
+    pub async fn is_busy(&mut self) -> Result<bool, SPI::Error> {
+        let mode: registers::Mode = self.read_reg().await?;
+        Ok(mode.trx_enabled)
+    }
+
+    /// Transmits a packet, returning once the A7105 has finished sending it
+    ///
+    /// This writes the packet into the TX FIFO, places the A7105 in [`Mode::Tx`] and then
+    /// polls the radio, using the provided delay between polls, until the transmission
+    /// has completed.
+    #[maybe_async::maybe_async]
+    pub async fn transmit<D: DelayNs>(
+        &mut self,
+        buf: &[u8],
+        delay: &mut D,
+    ) -> Result<(), SPI::Error> {
+        self.tx(buf).await?;
+        self.set_mode(Mode::Tx).await?;
+        while self.is_busy().await? {
+            delay.delay_us(Self::POLL_INTERVAL_US).await;
+        }
+        Ok(())
+    }
+
+    /// Waits up to `timeout_us` microseconds for a packet to be received, writing the
+    /// results into the provided buffer
+    ///
+    /// This places the A7105 in [`Mode::Rx`] and polls the radio, using the provided delay
+    /// between polls, until either a packet has been received or the timeout has elapsed.
+    /// If no packet arrives in time the A7105 is returned to [`Mode::Standby`] and
+    /// [`ReadPacketError::Timeout`] is returned.
+    ///
+    /// If the software AGC has been enabled through [`A7105::set_rx_gain`], the receiver
+    /// gain is adjusted based on the RSSI of every valid packet.
+    #[maybe_async::maybe_async]
+    pub async fn receive<D: DelayNs>(
+        &mut self,
+        buf: &mut [u8],
+        delay: &mut D,
+        timeout_us: u32,
+    ) -> Result<(), ReadPacketError<SPI::Error>> {
+        let mut remaining_us = timeout_us;
+        self.receive_within(buf, delay, &mut remaining_us).await
+    }
+
+    /// Receives a packet like [`A7105::receive`], deducting the time spent waiting from
+    /// `remaining_us` so that several receptions can share a single deadline
+    #[maybe_async::maybe_async]
+    pub(crate) async fn receive_within<D: DelayNs>(
+        &mut self,
+        buf: &mut [u8],
+        delay: &mut D,
+        remaining_us: &mut u32,
+    ) -> Result<(), ReadPacketError<SPI::Error>> {
+        self.set_mode(Mode::Rx).await?;
+        while self.is_busy().await? {
+            if *remaining_us == 0 {
+                self.set_mode(Mode::Standby).await?;
+                return Err(ReadPacketError::Timeout);
+            }
+            delay.delay_us(Self::POLL_INTERVAL_US).await;
+            *remaining_us = remaining_us.saturating_sub(Self::POLL_INTERVAL_US);
+        }
+        self.rx(buf).await?;
+
+        if let Some(mut agc) = self.agc {
+            let rssi: registers::RssiAdcOutput = self.read_reg().await?;
+            let gain = agc.update(rssi);
+            self.agc = Some(agc);
+            if let Some(gain) = gain {
+                self.write_reg(gain).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Configures the A7105 for the given on-air data rate, in bits per second
+    ///
+    /// The data rate divider is derived from the crystal frequency and the current
+    /// [`Clock`](registers::Clock) and [`Pll2`](registers::Pll2) settings, and rates that
+    /// cannot be produced exactly are rejected with [`ConfigError::UnsupportedDataRate`].
+    ///
+    /// The frequency deviation is set to the datasheet recommendation for the rate, roughly
+    /// 124KHz up to 50Kbps and 186KHz above, and the RX filter bandwidth to 500KHz. As the
+    /// [`Rx`](registers::Rx), [`Tx1`](registers::Tx1) and [`Tx2`](registers::Tx2) registers
+    /// are write only, all of their other fields are reset to their default values.
+    #[maybe_async::maybe_async]
+    pub async fn set_data_rate(
+        &mut self,
+        xtal_hz: u32,
+        bps: u32,
+    ) -> Result<(), ConfigError<SPI::Error>> {
+        let clock: registers::Clock = self.read_reg().await?;
+        let pll2: registers::Pll2 = self.read_reg().await?;
+
+        let data_rate = registers::DataRate::from_bps(clock.system_clock_hz(xtal_hz, &pll2), bps)
+            .ok_or(ConfigError::UnsupportedDataRate)?;
+        let tx1 = registers::Tx1::default();
+        let deviation_hz = if bps <= 50_000 { 124_000 } else { 186_000 };
+        let tx2 = registers::Tx2::from_deviation(pll2.pfd_hz(xtal_hz), tx1.fdp, deviation_hz)
+            .ok_or(ConfigError::UnsupportedDeviation)?;
+
+        self.write_reg_checked(data_rate).await?;
+        self.write_reg_checked(registers::Rx {
+            bandwidth: registers::Bandwidth::Khz500,
+            ..Default::default()
+        })
+        .await?;
+        self.write_reg_checked(tx1).await?;
+        self.write_reg_checked(tx2).await?;
+        Ok(())
+    }
+
+    /// Configures the transmit modulation
+    ///
+    /// The modulation is validated against the data rate currently configured on the A7105
+    /// and the provided RX bandwidth, which cannot be read back from the radio. Both
+    /// [`Tx1`](registers::Tx1) and [`Tx2`](registers::Tx2) are computed before either is
+    /// written, so a rejected modulation leaves the radio untouched. Refer to
+    /// [`Modulation::to_registers`](modulation::Modulation::to_registers) for details.
+    ///
+    /// The two registers are written in separate SPI transactions, as the A7105 does not
+    /// auto-increment register addresses, so the update is not atomic. The radio should not
+    /// be transmitting while the modulation changes, and an SPI error on the second write
+    /// leaves the new [`Tx1`](registers::Tx1) paired with the old [`Tx2`](registers::Tx2).
+    #[maybe_async::maybe_async]
+    pub async fn set_modulation(
+        &mut self,
+        xtal_hz: u32,
+        modulation: modulation::Modulation,
+        bandwidth: registers::Bandwidth,
+    ) -> Result<(), ConfigError<SPI::Error>> {
+        let clock: registers::Clock = self.read_reg().await?;
+        let pll2: registers::Pll2 = self.read_reg().await?;
+        let data_rate: registers::DataRate = self.read_reg().await?;
+
+        let bps = data_rate.bps(clock.system_clock_hz(xtal_hz, &pll2));
+        let (tx1, tx2) = modulation
+            .to_registers(pll2.pfd_hz(xtal_hz), bps, bandwidth)
+            .map_err(ConfigError::widen)?;
+
+        self.write_reg_checked(tx1).await?;
+        self.write_reg_checked(tx2).await?;
+        Ok(())
+    }
+
+    /// Sets the transmit power to the datasheet level closest to `dbm`, returning the level
+    /// that was selected
+    ///
+    /// Refer to [`TxPower::nearest`](registers::TxPower::nearest) for how the level is
+    /// chosen.
+    #[maybe_async::maybe_async]
+    pub async fn set_tx_power(&mut self, dbm: i8) -> Result<registers::TxPower, SPI::Error> {
+        let level = registers::TxPower::nearest(dbm);
+        self.write_reg(level.tx_test()).await?;
+        self.tx_power = level;
+        Ok(level)
+    }
+
+    /// Sets how the receiver gain is managed
+    ///
+    /// In [`RxGainMode::Manual`](agc::RxGainMode::Manual) the provided gain is written
+    /// once. In [`RxGainMode::Automatic`](agc::RxGainMode::Automatic) the gain starts at
+    /// its most sensitive setting and is adjusted by [`A7105::receive`] after every packet.
+    #[maybe_async::maybe_async]
+    pub async fn set_rx_gain(&mut self, mode: agc::RxGainMode) -> Result<(), SPI::Error> {
+        let (gain, agc) = match mode {
+            agc::RxGainMode::Manual(gain) => (gain, None),
+            agc::RxGainMode::Automatic(config) => {
+                let agc = agc::Agc::new(config);
+                (agc.gain(), Some(agc))
+            }
+        };
+        self.write_reg(gain).await?;
+        self.agc = agc;
+        Ok(())
+    }
+
+    /// Returns `true` if the supply voltage is above the given threshold
+    ///
+    /// The A7105 must be in either [`Mode::Standby`] or [`Mode::Pll`] for the battery
+    /// detector to operate. As [`BatteryDetectConfig`](registers::BatteryDetectConfig) is
+    /// write only, its regulator settings are taken from the [`shadow`] cache, falling back
+    /// to their default values if the cache is disabled. The cache records the threshold
+    /// with the detector disabled, as the A7105 clears the enable bit once it completes.
+    #[maybe_async::maybe_async]
+    pub async fn battery_above<D: DelayNs>(
+        &mut self,
+        threshold: registers::DetectThreshold,
+        delay: &mut D,
+    ) -> Result<bool, SPI::Error> {
+        let config = registers::BatteryDetectConfig {
+            detect_threshold: threshold,
+            detect_enabled: false,
+            ..self.cached_or_default()
+        };
+        let start = registers::BatteryDetectConfig {
+            detect_enabled: true,
+            ..config
+        };
+        let address = <registers::BatteryDetectConfig as registers::Register>::id();
+        self.write_bytes(address, &[u8::from(start)]).await?;
+        if let Some(shadow) = &mut self.shadow {
+            shadow.set(config);
+        }
+        delay.delay_us(Self::BATTERY_DETECT_US).await;
+        let result: registers::BatteryDetectResult = self.read_reg().await?;
+        Ok(result.voltage_above_threshold)
+    }
+
+    /// Brackets the supply voltage between two of the battery detector thresholds
+    ///
+    /// This performs a binary search over the eight thresholds, requiring at most four
+    /// measurements. Refer to [`A7105::battery_above`] for the required radio state.
+    #[maybe_async::maybe_async]
+    pub async fn measure_battery_voltage<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<battery::VoltageRange, SPI::Error> {
+        let thresholds = registers::DetectThreshold::ALL;
+        let (mut low, mut high) = (0, thresholds.len());
+        while low < high {
+            let mid = (low + high + 1) / 2;
+            if self.battery_above(thresholds[mid - 1], delay).await? {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        Ok(battery::VoltageRange::from_count(low))
+    }
+
+    /// Configures the given GIO pin as the SPI data output, switching the A7105 to 4-wire SPI
+    ///
+    /// Out of reset the A7105 uses 3-wire SPI, driving read data on the shared SDIO pin. A
+    /// SPI peripheral with separate MOSI and MISO lines can only write registers until this
+    /// has been called, after which read data is driven on the selected pin instead. The
+    /// other GIO pin is left unchanged, and as the GIO pin control registers are write only,
+    /// the output of the selected pin is reset to its default configuration.
+    #[maybe_async::maybe_async]
+    pub async fn enable_four_wire(&mut self, gio: registers::GioPin) -> Result<(), SPI::Error> {
+        let pin_function = registers::GpioPinFunction::Sdo;
+        match gio {
+            registers::GioPin::Gio1 => {
+                self.write_reg(registers::Gpio1PinControl {
+                    pin_function,
+                    ..Default::default()
+                })
+                .await
+            }
+            registers::GioPin::Gio2 => {
+                self.write_reg(registers::Gpio2PinControl {
+                    pin_function,
+                    ..Default::default()
+                })
+                .await
+            }
+        }
+    }
+
+    /// Returns the on-air data rate currently configured on the A7105, in bits per second
+    #[maybe_async::maybe_async]
+    pub async fn data_rate(&mut self, xtal_hz: u32) -> Result<u32, SPI::Error> {
+        let clock: registers::Clock = self.read_reg().await?;
+        let pll2: registers::Pll2 = self.read_reg().await?;
+        let data_rate: registers::DataRate = self.read_reg().await?;
+        Ok(data_rate.bps(clock.system_clock_hz(xtal_hz, &pll2)))
+    }
+
+    /// Reads every register of the A7105 into a [`RegisterSnapshot`](snapshot::RegisterSnapshot)
+    ///
+    /// Every address is read once, apart from the FIFO data register, which is skipped as
+    /// reading it would consume received data.
+    #[maybe_async::maybe_async]
+    pub async fn dump_registers(&mut self) -> Result<snapshot::RegisterSnapshot, SPI::Error> {
+        let mut raw = [0; snapshot::REGISTER_COUNT];
+        for (address, byte) in raw.iter_mut().enumerate() {
+            let address = address as u8;
+            if address == snapshot::FIFO_ADDRESS
+                || address == <registers::IdData as registers::Register>::id()
+            {
+                continue;
+            }
+            self.read_bytes(address, core::slice::from_mut(byte))
+                .await?;
+        }
+        let id = self.read_reg().await?;
+        Ok(snapshot::RegisterSnapshot::from_raw(raw, id))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::{run, MockSpi};
+
+    #[test]
+    fn test_write_verify() {
+        let spi = MockSpi::new();
+        let mut radio = A7105::new(spi.clone());
+        radio.set_write_verify(Some(2));
+        spi.sim().stuck = Some(0x0E);
+
+        let data_rate = registers::DataRate { rate: 9 };
+        run!(radio.write_reg(data_rate)).unwrap();
+        // The register is written once, then rewritten for each retry
+        let writes = spi
+            .sim()
+            .transactions
+            .iter()
+            .filter(|bytes| bytes[0] == 0x0E)
+            .count();
+        assert_eq!(writes, 3);
+
+        // Only the first failure is kept until it is taken
+        run!(radio.write_reg(registers::DataRate { rate: 4 })).unwrap();
+        let failure = VerifyError {
+            reg: 0x0E,
+            wrote: 9,
+            read: 0,
+        };
+        assert_eq!(radio.take_verify_error(), Some(failure));
+        assert_eq!(radio.take_verify_error(), None);
+
+        // Registers that read back correctly are not recorded
+        spi.sim().stuck = None;
+        run!(radio.write_reg(data_rate)).unwrap();
+        assert_eq!(radio.take_verify_error(), None);
+    }
+
+    #[test]
+    fn test_config_write_verify() {
+        let spi = MockSpi::new();
+        let mut radio = A7105::new(spi.clone());
+        radio.set_write_verify(Some(1));
+        spi.sim().stuck = Some(0x0E);
+
+        let result = run!(radio.set_data_rate(16_000_000, 250_000));
+        assert!(matches!(
+            result,
+            Err(ConfigError::VerifyError(VerifyError { reg: 0x0E, .. }))
+        ));
+        // The failure is returned rather than recorded
+        assert_eq!(radio.take_verify_error(), None);
+
+        spi.sim().stuck = None;
+        run!(radio.set_data_rate(16_000_000, 250_000)).unwrap();
+    }
 }
@@ -0,0 +1,372 @@
+//! Coordinating several A7105s sharing one SPI bus
+//!
+//! Diversity receivers and dual protocol transmitters pair two or more A7105s, usually on
+//! the same SPI bus with a chip select each. Every radio only needs its own `SpiDevice`,
+//! which shared bus implementations such as those of
+//! [`embedded-hal-bus`](https://crates.io/crates/embedded-hal-bus) provide, so a
+//! [`RadioManager`] simply owns one [`A7105`] per device.
+//!
+//! The manager tracks the channel every radio is tuned to and refuses to use radios
+//! together when that would have them interfere:
+//!
+//! - [`RadioManager::transmit_while_listening`] transmits on one radio while another keeps
+//!   listening, as long as their channels are at least `min_spacing` channels apart
+//! - [`RadioManager::diversity_receive`] listens on every radio tuned to a common channel
+//!   and keeps the packet received with the strongest RSSI
+//!
+//! ```ignore
+//! use a7105::manager::RadioManager;
+//! use a7105::prelude::*;
+//! use core::cell::RefCell;
+//! use embedded_hal_bus::spi::RefCellDevice;
+//!
+//! # let (spi_bus, cs1, cs2, mut delay) = unimplemented!();
+//! let bus = RefCell::new(spi_bus);
+//! let mut radios = RadioManager::new(
+//!     [
+//!         A7105::new(RefCellDevice::new(&bus, cs1, delay.clone()).unwrap()),
+//!         A7105::new(RefCellDevice::new(&bus, cs2, delay.clone()).unwrap()),
+//!     ],
+//!     4,
+//! );
+//!
+//! radios.set_channel(0, 10).await.unwrap();
+//! radios.set_channel(1, 80).await.unwrap();
+//! radios.transmit_while_listening(0, 1, &[1, 2, 3, 4], &mut delay).await.unwrap();
+//! ```
+
+use crate::{
+    commands::Mode,
+    registers::{self, Pll1, RssiAdcOutput},
+    ManagerError, PacketError, A7105,
+};
+
+#[cfg(feature = "blocking")]
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs, spi::SpiDevice};
+
+/// How long the remaining radios may keep receiving once one radio has received a packet
+///
+/// Radios receiving the same packet finish within a bit time of each other, so this only
+/// needs to cover a few polls of the bus.
+const DIVERSITY_SETTLE_US: u32 = 200;
+
+/// Returns `true` if radios on the two channels may not be used at the same time
+fn collides(a: u8, b: u8, min_spacing: u8) -> bool {
+    a.abs_diff(b) < min_spacing
+}
+
+/// Returns the index of the strongest signal, the A7105 reporting a lower RSSI voltage for a
+/// stronger signal
+fn strongest(voltages: &[Option<f32>]) -> Option<usize> {
+    voltages
+        .iter()
+        .enumerate()
+        .filter_map(|(index, voltage)| voltage.map(|voltage| (index, voltage)))
+        .reduce(|best, next| if next.1 < best.1 { next } else { best })
+        .map(|(index, _)| index)
+}
+
+/// Owns and coordinates `N` radios
+///
+/// Refer to the [module level documentation](self) for an overview.
+pub struct RadioManager<SPI, const N: usize> {
+    radios: [A7105<SPI>; N],
+    channels: [Option<u8>; N],
+    min_spacing: u8,
+}
+
+impl<SPI, const N: usize> RadioManager<SPI, N> {
+    /// Constructs a new [`RadioManager`] from the provided radios
+    ///
+    /// Radios transmitting and listening at the same time must be tuned at least
+    /// `min_spacing` channels apart. The channels of the radios are not known until they have
+    /// been set through [`RadioManager::set_channel`].
+    pub fn new(radios: [A7105<SPI>; N], min_spacing: u8) -> Self {
+        Self {
+            radios,
+            channels: [None; N],
+            min_spacing,
+        }
+    }
+
+    /// Destroys this [`RadioManager`], returning the radios
+    pub fn release(self) -> [A7105<SPI>; N] {
+        self.radios
+    }
+
+    /// Returns the radio at `index`, or `None` if there is no such radio
+    ///
+    /// The manager can not observe registers written directly, so the channel of the radio
+    /// is forgotten and must be set again through [`RadioManager::set_channel`] before the
+    /// radio is used with the others.
+    pub fn radio(&mut self, index: usize) -> Option<&mut A7105<SPI>> {
+        let radio = self.radios.get_mut(index)?;
+        self.channels[index] = None;
+        Some(radio)
+    }
+
+    /// Returns the channel the radio at `index` was last tuned to, or `None` if unknown
+    pub fn channel(&self, index: usize) -> Option<u8> {
+        self.channels.get(index).copied().flatten()
+    }
+}
+
+impl<SPI: SpiDevice, const N: usize> RadioManager<SPI, N> {
+    /// Tunes the radio at `index` to the given channel
+    #[maybe_async::maybe_async]
+    pub async fn set_channel(
+        &mut self,
+        index: usize,
+        channel: u8,
+    ) -> Result<(), ManagerError<SPI::Error>> {
+        let radio = self
+            .radios
+            .get_mut(index)
+            .ok_or(ManagerError::InvalidRadio)?;
+        // Forget the channel first, as a failed write leaves it unknown
+        self.channels[index] = None;
+        radio.write_reg(Pll1 { channel }).await?;
+        self.channels[index] = Some(channel);
+        Ok(())
+    }
+
+    /// Tunes every radio to the given channel, ready for [`RadioManager::diversity_receive`]
+    #[maybe_async::maybe_async]
+    pub async fn set_diversity_channel(
+        &mut self,
+        channel: u8,
+    ) -> Result<(), ManagerError<SPI::Error>> {
+        for index in 0..N {
+            self.set_channel(index, channel).await?;
+        }
+        Ok(())
+    }
+
+    /// Transmits a packet on the radio at `tx` while the radio at `rx` is placed in
+    /// [`Mode::Rx`], returning once the transmission has completed
+    ///
+    /// Both radios must have been tuned through [`RadioManager::set_channel`] at least
+    /// `min_spacing` channels apart, otherwise [`ManagerError::ChannelCollision`] is returned
+    /// without either radio being touched. The listening radio is left in [`Mode::Rx`], and
+    /// its packet can then be collected through [`RadioManager::radio`], after which its
+    /// channel has to be set again.
+    #[maybe_async::maybe_async]
+    pub async fn transmit_while_listening<D: DelayNs>(
+        &mut self,
+        tx: usize,
+        rx: usize,
+        buf: &[u8],
+        delay: &mut D,
+    ) -> Result<(), ManagerError<SPI::Error>> {
+        if tx >= N || rx >= N || tx == rx {
+            return Err(ManagerError::InvalidRadio);
+        }
+        match (self.channels[tx], self.channels[rx]) {
+            (Some(a), Some(b)) if !collides(a, b, self.min_spacing) => {}
+            _ => return Err(ManagerError::ChannelCollision),
+        }
+
+        self.radios[rx].set_mode(Mode::Rx).await?;
+        self.radios[tx].transmit(buf, delay).await?;
+        Ok(())
+    }
+
+    /// Waits up to `timeout_us` microseconds for a packet to be received on any radio,
+    /// writing the copy received with the strongest RSSI into the provided buffer and
+    /// returning the index of the radio it was read from
+    ///
+    /// Every radio must have been tuned to the same channel, for example through
+    /// [`RadioManager::set_diversity_channel`], otherwise [`ManagerError::ChannelMismatch`] is
+    /// returned. Once one radio has received a packet the others are given a short grace
+    /// period to finish, after which any radio still receiving is returned to
+    /// [`Mode::Standby`]. If no radio received a valid packet, the error of the first
+    /// invalid packet is returned, or [`ManagerError::Timeout`] if none was received at all.
+    #[maybe_async::maybe_async]
+    pub async fn diversity_receive<D: DelayNs>(
+        &mut self,
+        buf: &mut [u8],
+        delay: &mut D,
+        timeout_us: u32,
+    ) -> Result<usize, ManagerError<SPI::Error>> {
+        let channel = self.channels.first().copied().flatten();
+        if channel.is_none() || self.channels.iter().any(|c| *c != channel) {
+            return Err(ManagerError::ChannelMismatch);
+        }
+
+        for radio in self.radios.iter_mut() {
+            radio.set_mode(Mode::Rx).await?;
+        }
+        let mut done = [false; N];
+        let mut waited_us = 0;
+        let mut deadline_us = timeout_us;
+        loop {
+            for (radio, done) in self.radios.iter_mut().zip(done.iter_mut()) {
+                if !*done {
+                    *done = !radio.is_busy().await?;
+                }
+            }
+            if done.iter().all(|done| *done) {
+                break;
+            }
+            if done.iter().any(|done| *done) {
+                deadline_us = deadline_us.min(waited_us + DIVERSITY_SETTLE_US);
+            }
+            if waited_us >= deadline_us {
+                break;
+            }
+            delay.delay_us(A7105::<SPI>::POLL_INTERVAL_US).await;
+            waited_us += A7105::<SPI>::POLL_INTERVAL_US;
+        }
+
+        let mut voltages = [None; N];
+        let mut packet_error: Option<PacketError> = None;
+        for ((radio, done), voltage) in self.radios.iter_mut().zip(done).zip(voltages.iter_mut()) {
+            if !done {
+                radio.set_mode(Mode::Standby).await?;
+                continue;
+            }
+            let mode: registers::Mode = radio.read_reg().await?;
+            if !mode.crc_pass || !mode.fec_pass {
+                packet_error.get_or_insert(mode.into());
+                continue;
+            }
+            let rssi: RssiAdcOutput = radio.read_reg().await?;
+            *voltage = Some(rssi.voltage);
+        }
+
+        match strongest(&voltages) {
+            Some(index) => {
+                self.radios[index].rx(buf).await?;
+                Ok(index)
+            }
+            None => Err(packet_error.map_or(ManagerError::Timeout, ManagerError::PacketError)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        mock::{run, MockDelay, MockSpi},
+        registers::Register as _,
+    };
+
+    fn manager() -> (RadioManager<MockSpi, 2>, [MockSpi; 2]) {
+        let spis = [MockSpi::new(), MockSpi::new()];
+        let radios = spis.clone().map(A7105::new);
+        (RadioManager::new(radios, 4), spis)
+    }
+
+    #[test]
+    fn test_collides() {
+        assert!(collides(10, 10, 1));
+        assert!(collides(10, 13, 4));
+        assert!(collides(13, 10, 4));
+        assert!(!collides(10, 14, 4));
+        assert!(!collides(10, 10, 0));
+    }
+
+    #[test]
+    fn test_strongest() {
+        assert_eq!(strongest(&[]), None);
+        assert_eq!(strongest(&[None, None]), None);
+        assert_eq!(strongest(&[Some(0.8), None, Some(0.3)]), Some(2));
+        // Ties keep the first radio
+        assert_eq!(strongest(&[None, Some(0.5), Some(0.5)]), Some(1));
+    }
+
+    #[test]
+    fn test_radio_forgets_channel() {
+        let (mut radios, _) = manager();
+        run!(radios.set_channel(0, 10)).unwrap();
+        assert_eq!(radios.channel(0), Some(10));
+
+        assert!(radios.radio(0).is_some());
+        assert_eq!(radios.channel(0), None);
+        assert!(radios.radio(2).is_none());
+    }
+
+    #[test]
+    fn test_transmit_while_listening() {
+        let (mut radios, spis) = manager();
+        let mut delay = MockDelay::default();
+        run!(radios.set_channel(0, 10)).unwrap();
+        run!(radios.set_channel(1, 12)).unwrap();
+
+        // Too close together, so neither radio is touched
+        let transactions = [0, 1].map(|i| spis[i].sim().transactions.len());
+        assert_eq!(
+            run!(radios.transmit_while_listening(0, 1, &[1, 2, 3], &mut delay)),
+            Err(ManagerError::ChannelCollision)
+        );
+        assert_eq!(
+            [0, 1].map(|i| spis[i].sim().transactions.len()),
+            transactions
+        );
+        assert_eq!(
+            run!(radios.transmit_while_listening(0, 0, &[1, 2, 3], &mut delay)),
+            Err(ManagerError::InvalidRadio)
+        );
+
+        run!(radios.set_channel(1, 80)).unwrap();
+        run!(radios.transmit_while_listening(0, 1, &[1, 2, 3], &mut delay)).unwrap();
+        assert_eq!(spis[0].sim().sent.len(), 1);
+        assert_eq!(spis[0].sim().sent[0][..], [1, 2, 3]);
+        assert!(spis[1].sim().sent.is_empty());
+        assert!(spis[1].sim().in_rx());
+    }
+
+    #[test]
+    fn test_diversity_receive() {
+        let (mut radios, spis) = manager();
+        let mut delay = MockDelay::default();
+        let mut buf = [0; 3];
+
+        run!(radios.set_channel(0, 10)).unwrap();
+        run!(radios.set_channel(1, 11)).unwrap();
+        assert_eq!(
+            run!(radios.diversity_receive(&mut buf, &mut delay, 1_000)),
+            Err(ManagerError::ChannelMismatch)
+        );
+
+        // The second radio hears the packet with the lower RSSI voltage, so the stronger signal
+        run!(radios.set_diversity_channel(10)).unwrap();
+        spis[0].sim().push(&[1, 1, 1]);
+        spis[0].sim().set_reg(RssiAdcOutput::id(), 0xA0);
+        spis[1].sim().push(&[2, 2, 2]);
+        spis[1].sim().set_reg(RssiAdcOutput::id(), 0x40);
+        assert_eq!(
+            run!(radios.diversity_receive(&mut buf, &mut delay, 1_000)),
+            Ok(1)
+        );
+        assert_eq!(buf, [2, 2, 2]);
+
+        // A radio still receiving once the grace period is over is returned to standby
+        spis[0].sim().push(&[1, 1, 1]);
+        assert_eq!(
+            run!(radios.diversity_receive(&mut buf, &mut delay, 1_000)),
+            Ok(0)
+        );
+        assert!(!spis[1].sim().in_rx());
+
+        spis[1].sim().push_corrupt(&[2, 2, 2]);
+        assert_eq!(
+            run!(radios.diversity_receive(&mut buf, &mut delay, 1_000)),
+            Err(ManagerError::PacketError(PacketError {
+                fec_failed: false,
+                crc_failed: true,
+            }))
+        );
+
+        delay.elapsed_us = 0;
+        assert_eq!(
+            run!(radios.diversity_receive(&mut buf, &mut delay, 1_000)),
+            Err(ManagerError::Timeout)
+        );
+        assert!(delay.elapsed_us >= 1_000);
+        assert!(spis.iter().all(|spi| !spi.sim().in_rx()));
+    }
+}
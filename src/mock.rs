@@ -0,0 +1,352 @@
+//! A simulated A7105 for testing the drivers built on top of [`A7105`](crate::A7105)
+//!
+//! [`MockSpi`] decodes the SPI transactions issued by the driver and models just enough of
+//! the radio to exercise them: the register file, the mode strobes and the FIFO. Packets
+//! queued with [`Sim::push`] are received once the simulated radio is placed in RX, every
+//! transmitted packet is recorded, and a responder can queue a reply to each transmission.
+
+extern crate std;
+
+use core::{
+    cell::{RefCell, RefMut},
+    convert::Infallible,
+};
+#[cfg(feature = "async")]
+use core::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+use embedded_hal::spi::{ErrorType, Operation};
+use std::{boxed::Box, collections::VecDeque, rc::Rc, vec::Vec};
+
+const MODE: u8 = 0x00;
+const FIFO: u8 = 0x05;
+const ID: u8 = 0x06;
+const BATTERY: u8 = 0x27;
+const READ_FLAG: u8 = 0x40;
+const STROBE_FLAG: u8 = 0x80;
+
+/// Returns a waker that does nothing, as futures are polled in a loop
+#[cfg(feature = "async")]
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(core::ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    // SAFETY: the waker does nothing, so its data pointer is never used
+    unsafe { Waker::from_raw(clone(core::ptr::null())) }
+}
+
+/// Runs a future to completion, for testing the `async` driver without an executor
+#[cfg(feature = "async")]
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// Polls a future up to `polls` times, returning `None` if it did not complete, for
+/// testing tasks that never return
+#[cfg(feature = "embassy")]
+pub(crate) fn poll_times<F: Future>(
+    mut future: core::pin::Pin<&mut F>,
+    polls: usize,
+) -> Option<F::Output> {
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    (0..polls).find_map(|_| match future.as_mut().poll(&mut cx) {
+        Poll::Ready(output) => Some(output),
+        Poll::Pending => None,
+    })
+}
+
+/// Evaluates a driver call, blocking on it when built with the `async` feature
+#[cfg(feature = "async")]
+macro_rules! run {
+    ($call:expr) => {
+        $crate::mock::block_on($call)
+    };
+}
+
+#[cfg(feature = "blocking")]
+macro_rules! run {
+    ($call:expr) => {
+        $call
+    };
+}
+
+pub(crate) use run;
+
+/// Called with every transmitted packet, returning a reply to receive
+pub(crate) type Responder = Box<dyn FnMut(&[u8]) -> Option<Vec<u8>>>;
+
+/// A packet waiting to be received by the simulated radio
+struct Incoming {
+    data: Vec<u8>,
+    crc_ok: bool,
+}
+
+/// The state of the simulated radio
+pub(crate) struct Sim {
+    regs: [u8; 0x33],
+    id: [u8; 4],
+    mode_strobe: u8,
+    rx_active: bool,
+    rx_countdown: u32,
+    tx_countdown: u32,
+    crc_ok: bool,
+    rx_fifo: Vec<u8>,
+    tx_fifo: Vec<u8>,
+    incoming: VecDeque<Incoming>,
+    /// How many polls of the mode register a packet takes to arrive once in RX
+    pub(crate) airtime_polls: u32,
+    /// How many polls of the mode register a transmission remains busy for
+    pub(crate) tx_polls: u32,
+    /// Every packet transmitted, in order
+    pub(crate) sent: Vec<Vec<u8>>,
+    /// The bytes written in every SPI transaction, in order
+    pub(crate) transactions: Vec<Vec<u8>>,
+    /// Called with every transmitted packet, returning a reply to receive
+    pub(crate) responder: Option<Responder>,
+    /// The supply voltage compared against by the battery detector
+    pub(crate) supply_mv: u16,
+    /// A register whose writes are lost, as on a glitching bus
+    pub(crate) stuck: Option<u8>,
+}
+
+impl Sim {
+    /// Queues a valid packet to be received
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        self.incoming.push_back(Incoming {
+            data: data.to_vec(),
+            crc_ok: true,
+        });
+    }
+
+    /// Queues a packet that fails its CRC check
+    pub(crate) fn push_corrupt(&mut self, data: &[u8]) {
+        self.incoming.push_back(Incoming {
+            data: data.to_vec(),
+            crc_ok: false,
+        });
+    }
+
+    /// Sets the value read back from a register
+    pub(crate) fn set_reg(&mut self, address: u8, value: u8) {
+        self.regs[usize::from(address)] = value;
+    }
+
+    /// Returns `true` while the simulated radio is in RX
+    pub(crate) fn in_rx(&self) -> bool {
+        self.rx_active
+    }
+
+    fn mode(&mut self) -> u8 {
+        // The crystal runs from standby upwards, and the PLL from PLL mode upwards
+        let oscillators = match self.mode_strobe {
+            0xA0 => 0b0000_1000,
+            0xB0..=0xD0 => 0b0000_1100,
+            _ => 0,
+        };
+        if self.tx_countdown > 0 {
+            self.tx_countdown -= 1;
+            return 0b0110_0011 | oscillators;
+        }
+
+        if self.rx_active {
+            if self.rx_countdown > 0 {
+                self.rx_countdown -= 1;
+            } else if let Some(packet) = self.incoming.pop_front() {
+                self.rx_fifo = packet.data;
+                self.crc_ok = packet.crc_ok;
+                self.rx_active = false;
+            }
+        }
+        // FEC and CRC pass flags, then TRX enabled while receiving
+        0b0100_0000 | u8::from(self.crc_ok) << 5 | u8::from(self.rx_active) << 1 | oscillators
+    }
+
+    fn strobe(&mut self, strobe: u8) {
+        if strobe <= 0xD0 {
+            self.mode_strobe = strobe;
+            self.tx_countdown = 0;
+        }
+        match strobe {
+            // FIFO pointer resets
+            0xE0 | 0xF0 => {}
+            0xC0 => {
+                self.rx_active = true;
+                self.rx_countdown = self.airtime_polls;
+            }
+            0xD0 => {
+                self.rx_active = false;
+                self.tx_countdown = self.tx_polls;
+                let packet = self.tx_fifo.clone();
+                if let Some(reply) = self.responder.as_mut().and_then(|f| f(&packet)) {
+                    self.push(&reply);
+                }
+                self.sent.push(packet);
+            }
+            _ => self.rx_active = false,
+        }
+    }
+
+    fn read(&mut self, address: u8, buf: &mut [u8]) {
+        match address {
+            MODE => buf.fill(self.mode()),
+            FIFO => {
+                buf.fill(0);
+                let len = buf.len().min(self.rx_fifo.len());
+                buf[..len].copy_from_slice(&self.rx_fifo[..len]);
+            }
+            ID => buf.copy_from_slice(&self.id[..buf.len()]),
+            _ => buf.fill(self.regs[usize::from(address)]),
+        }
+    }
+
+    fn write(&mut self, address: u8, data: &[u8]) {
+        if self.stuck == Some(address) {
+            return;
+        }
+        match address {
+            MODE => {
+                self.regs = [0; 0x33];
+                self.id = [0; 4];
+                self.mode_strobe = 0xA0;
+                self.rx_active = false;
+                self.tx_countdown = 0;
+            }
+            FIFO => self.tx_fifo = data.to_vec(),
+            ID => self.id[..data.len()].copy_from_slice(data),
+            // A detection completes immediately, clearing the enable bit and setting the flag
+            BATTERY if data[0] & 0b1 != 0 => {
+                let threshold_mv = 2_000 + 100 * u16::from(data[0] >> 1 & 0b111);
+                let above = u8::from(self.supply_mv >= threshold_mv);
+                self.regs[usize::from(address)] = data[0] & 0b1110_1110 | above << 4;
+            }
+            _ => self.regs[usize::from(address)] = data[0],
+        }
+    }
+}
+
+impl Default for Sim {
+    fn default() -> Self {
+        Self {
+            regs: [0; 0x33],
+            id: [0; 4],
+            mode_strobe: 0xA0,
+            rx_active: false,
+            rx_countdown: 0,
+            tx_countdown: 0,
+            crc_ok: true,
+            rx_fifo: Vec::new(),
+            tx_fifo: Vec::new(),
+            incoming: VecDeque::new(),
+            airtime_polls: 0,
+            tx_polls: 0,
+            sent: Vec::new(),
+            transactions: Vec::new(),
+            responder: None,
+            supply_mv: 3_300,
+            stuck: None,
+        }
+    }
+}
+
+/// A `SpiDevice` connected to a simulated A7105, shared with the test inspecting it
+#[derive(Clone, Default)]
+pub(crate) struct MockSpi(Rc<RefCell<Sim>>);
+
+impl MockSpi {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn sim(&self) -> RefMut<'_, Sim> {
+        self.0.borrow_mut()
+    }
+
+    fn process(&mut self, operations: &mut [Operation<'_, u8>]) {
+        let mut sim = self.sim();
+        let mut written = Vec::new();
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => written.extend_from_slice(bytes),
+                Operation::Read(buf) => sim.read(written[0] & !READ_FLAG, buf),
+                Operation::DelayNs(_) => {}
+                Operation::Transfer(..) | Operation::TransferInPlace(..) => {
+                    panic!("the A7105 is never accessed full duplex")
+                }
+            }
+        }
+
+        match written.first() {
+            Some(&header) if header & STROBE_FLAG != 0 => sim.strobe(header),
+            Some(&header) if header & READ_FLAG == 0 => sim.write(header, &written[1..]),
+            _ => {}
+        }
+        sim.transactions.push(written);
+    }
+}
+
+impl ErrorType for MockSpi {
+    type Error = Infallible;
+}
+
+#[cfg(feature = "blocking")]
+impl embedded_hal::spi::SpiDevice for MockSpi {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Infallible> {
+        self.process(operations);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::spi::SpiDevice for MockSpi {
+    async fn transaction(
+        &mut self,
+        operations: &mut [Operation<'_, u8>],
+    ) -> Result<(), Infallible> {
+        self.process(operations);
+        Ok(())
+    }
+}
+
+/// A delay that returns immediately, recording how long it was asked to wait
+#[derive(Default)]
+pub(crate) struct MockDelay {
+    pub(crate) elapsed_us: u64,
+}
+
+#[cfg(feature = "blocking")]
+impl embedded_hal::delay::DelayNs for MockDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.elapsed_us += u64::from(ns / 1_000);
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for MockDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.elapsed_us += u64::from(ns / 1_000);
+        // Yield once, so that tasks polling the radio forever can still be stopped
+        let mut yielded = false;
+        core::future::poll_fn(|_| {
+            if yielded {
+                Poll::Ready(())
+            } else {
+                yielded = true;
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
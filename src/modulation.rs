@@ -0,0 +1,193 @@
+//! Modulation configuration in terms of physical units
+//!
+//! The A7105 expresses its frequency deviation through the `FDP` exponent in
+//! [`Tx1`](crate::registers::Tx1) and the `FD` mantissa in [`Tx2`](crate::registers::Tx2),
+//! scaled by the PLL comparison frequency. A [`Modulation`] describes the desired waveform in
+//! Hz instead, and takes care of picking register values and checking that the result can
+//! be demodulated at the configured data rate and RX bandwidth.
+//!
+//! ```ignore
+//! use a7105::modulation::Modulation;
+//! use a7105::prelude::*;
+//!
+//! # let a7105_spi_peripheral = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//!
+//! radio.set_data_rate(16_000_000, 250_000).await.unwrap();
+//! radio
+//!     .set_modulation(16_000_000, Modulation::gfsk(186_000), registers::Bandwidth::Khz500)
+//!     .await
+//!     .unwrap();
+//! ```
+
+use crate::registers::{Bandwidth, MovingAverage, Tx1, Tx2};
+use crate::ConfigError;
+use core::convert::Infallible;
+use defmt::Format;
+
+/// The shape of the frequency shift keying applied to transmitted data
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub enum ModulationKind {
+    /// Plain FSK, optionally smoothed with a moving average
+    Fsk,
+    /// FSK shaped by the internal Gaussian filter (BT = 0.7)
+    Gfsk,
+}
+
+/// A transmit modulation expressed in physical units
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct Modulation {
+    /// The shape of the modulation
+    pub kind: ModulationKind,
+    /// The peak frequency deviation from the carrier, in Hz
+    pub deviation_hz: u32,
+    /// The moving average applied to FSK data, which must be `None` for GFSK
+    pub moving_average: Option<MovingAverage>,
+}
+
+impl Modulation {
+    /// Constructs an unfiltered FSK modulation with the given deviation
+    pub const fn fsk(deviation_hz: u32) -> Self {
+        Self {
+            kind: ModulationKind::Fsk,
+            deviation_hz,
+            moving_average: None,
+        }
+    }
+
+    /// Constructs a GFSK modulation with the given deviation
+    pub const fn gfsk(deviation_hz: u32) -> Self {
+        Self {
+            kind: ModulationKind::Gfsk,
+            deviation_hz,
+            moving_average: None,
+        }
+    }
+
+    /// Smooths FSK data with the given moving average
+    pub const fn with_moving_average(mut self, moving_average: MovingAverage) -> Self {
+        self.moving_average = Some(moving_average);
+        self
+    }
+
+    /// Computes the [`Tx1`] and [`Tx2`] values for this modulation
+    ///
+    /// The smallest `FDP` able to represent the deviation is used, as it gives the finest
+    /// resolution. The deviation produced by the chosen register values, rather than the one
+    /// requested, is then checked: the modulation is rejected if it does not fit within half
+    /// of the RX bandwidth, or if the modulation index at `bps` would fall below 0.5.
+    pub fn to_registers(
+        &self,
+        pfd_hz: u32,
+        bps: u32,
+        bandwidth: Bandwidth,
+    ) -> Result<(Tx1, Tx2), ConfigError<Infallible>> {
+        if self.kind == ModulationKind::Gfsk && self.moving_average.is_some() {
+            return Err(ConfigError::ConflictingModulation);
+        }
+
+        let (fdp, tx2) = (0..=0b111)
+            .find_map(|fdp| {
+                Tx2::from_deviation(pfd_hz, fdp, self.deviation_hz).map(|tx2| (fdp, tx2))
+            })
+            .ok_or(ConfigError::UnsupportedDeviation)?;
+
+        let deviation_hz = tx2.deviation_hz(pfd_hz, fdp);
+        let bandwidth_hz = match bandwidth {
+            Bandwidth::Khz250 => 250_000,
+            Bandwidth::Khz500 => 500_000,
+        };
+        if deviation_hz > bandwidth_hz / 2 {
+            return Err(ConfigError::DeviationExceedsBandwidth);
+        }
+        if u64::from(deviation_hz) * 4 < u64::from(bps) {
+            return Err(ConfigError::ModulationIndexTooLow);
+        }
+
+        let tx1 = Tx1 {
+            moving_average: self.moving_average,
+            filter_enable: self.kind == ModulationKind::Gfsk,
+            fdp,
+            ..Default::default()
+        };
+        Ok((tx1, tx2))
+    }
+
+    /// Decodes the modulation, including the effective deviation, produced by the given
+    /// register values
+    pub fn from_registers(tx1: &Tx1, tx2: &Tx2, pfd_hz: u32) -> Self {
+        Self {
+            kind: if tx1.filter_enable {
+                ModulationKind::Gfsk
+            } else {
+                ModulationKind::Fsk
+            },
+            deviation_hz: tx2.deviation_hz(pfd_hz, tx1.fdp),
+            moving_average: tx1.moving_average,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_modulation_round_trip() {
+        let (tx1, tx2) = Modulation::gfsk(186_000)
+            .to_registers(16_000_000, 250_000, Bandwidth::Khz500)
+            .unwrap();
+        assert!(tx1.filter_enable);
+        // Listed as 186kHz in the datasheet
+        assert_eq!(tx2.deviation_hz(16_000_000, tx1.fdp), 186_035);
+        assert_eq!(
+            Modulation::from_registers(&tx1, &tx2, 16_000_000),
+            Modulation::gfsk(186_035)
+        );
+
+        let modulation = Modulation::fsk(62_500).with_moving_average(MovingAverage::TwoBit);
+        let (tx1, tx2) = modulation
+            .to_registers(16_000_000, 100_000, Bandwidth::Khz250)
+            .unwrap();
+        assert_eq!(tx1.moving_average, Some(MovingAverage::TwoBit));
+        assert_eq!(
+            Modulation::from_registers(&tx1, &tx2, 16_000_000),
+            Modulation {
+                deviation_hz: 62_011,
+                ..modulation
+            }
+        );
+    }
+
+    #[test]
+    fn test_modulation_validation() {
+        let gfsk = Modulation::gfsk(186_000);
+        assert_eq!(
+            gfsk.to_registers(16_000_000, 250_000, Bandwidth::Khz250),
+            Err(ConfigError::DeviationExceedsBandwidth)
+        );
+        assert_eq!(
+            Modulation::gfsk(100_000).to_registers(16_000_000, 500_000, Bandwidth::Khz500),
+            Err(ConfigError::ModulationIndexTooLow)
+        );
+        assert_eq!(
+            gfsk.with_moving_average(MovingAverage::FourBit)
+                .to_registers(16_000_000, 250_000, Bandwidth::Khz500),
+            Err(ConfigError::ConflictingModulation)
+        );
+        assert_eq!(
+            Modulation::fsk(50).to_registers(16_000_000, 2, Bandwidth::Khz500),
+            Err(ConfigError::UnsupportedDeviation)
+        );
+
+        // The requested deviations pass, but the ones the registers produce do not
+        assert_eq!(
+            Modulation::gfsk(125_000).to_registers(24_000_000, 250_000, Bandwidth::Khz250),
+            Err(ConfigError::DeviationExceedsBandwidth)
+        );
+        assert_eq!(
+            Modulation::fsk(62_500).to_registers(16_000_000, 250_000, Bandwidth::Khz250),
+            Err(ConfigError::ModulationIndexTooLow)
+        );
+    }
+}
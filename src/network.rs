@@ -0,0 +1,586 @@
+//! Addressed star-topology networking on top of [`A7105::transmit`] and [`A7105::receive`]
+//!
+//! All radios in a network share the same [`IdData`](crate::registers::IdData) sync word,
+//! which keeps traffic from other networks out of the FIFO. Within a network every frame
+//! additionally carries a source and destination [address](Address), allowing a single
+//! [`Hub`] to talk to many [`Node`]s.
+//!
+//! To avoid collisions nodes never transmit on their own. Instead the hub polls each
+//! registered node in turn, handing it a time slot in which it may send a single frame back
+//! to the hub. Frames from the hub may be addressed to a single node or broadcast to all of
+//! them.
+//!
+//! Frames are always a fixed `N` bytes long, matching the FIFO length configured through
+//! [`Fifo1`](crate::registers::Fifo1), and consist of a 4 byte header followed by the payload.
+//!
+//! ```ignore
+//! use a7105::network::{Hub, HubConfig};
+//! use a7105::prelude::*;
+//!
+//! # let (a7105_spi_peripheral, mut delay) = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//!
+//! let mut hub: Hub<16, 4> = Hub::new(HubConfig::default());
+//! hub.add_node(0x01).unwrap();
+//! hub.add_node(0x02).unwrap();
+//!
+//! // Tell every node something
+//! hub.send(&mut radio, &mut delay, a7105::network::BROADCAST, b"sync").await.unwrap();
+//!
+//! // Give the next node its slot
+//! let mut buf = [0; 16];
+//! if let Some(uplink) = hub.poll_next(&mut radio, &mut delay, &mut buf).await.unwrap() {
+//!     let data = &buf[..uplink.len];
+//! }
+//! ```
+
+use crate::{NetworkError, ReadPacketError, A7105};
+use core::convert::Infallible;
+use defmt::Format;
+
+#[cfg(feature = "blocking")]
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs, spi::SpiDevice};
+
+/// The address of a radio within a network
+pub type Address = u8;
+
+/// The address of the [`Hub`] of every network
+pub const HUB: Address = 0x00;
+
+/// The destination address used to send a frame to every [`Node`] in the network
+pub const BROADCAST: Address = 0xFF;
+
+const HEADER_LEN: usize = 4;
+const KIND_DATA: u8 = 0x01;
+const KIND_POLL: u8 = 0x02;
+
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+enum FrameKind {
+    Data,
+    Poll,
+}
+
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+struct Header {
+    dst: Address,
+    src: Address,
+    kind: FrameKind,
+}
+
+/// Writes a frame into `frame`, returning `None` if the payload does not fit
+fn encode(header: Header, payload: &[u8], frame: &mut [u8]) -> Option<()> {
+    let end = HEADER_LEN + payload.len();
+    if end > frame.len() || payload.len() > u8::MAX as usize {
+        return None;
+    }
+
+    frame[0] = header.dst;
+    frame[1] = header.src;
+    frame[2] = match header.kind {
+        FrameKind::Data => KIND_DATA,
+        FrameKind::Poll => KIND_POLL,
+    };
+    frame[3] = payload.len() as u8;
+    frame[HEADER_LEN..end].copy_from_slice(payload);
+    frame[end..].fill(0);
+    Some(())
+}
+
+/// Parses a received frame, returning `None` if it is malformed
+fn decode(frame: &[u8]) -> Option<(Header, &[u8])> {
+    if frame.len() < HEADER_LEN {
+        return None;
+    }
+
+    let kind = match frame[2] {
+        KIND_DATA => FrameKind::Data,
+        KIND_POLL => FrameKind::Poll,
+        _ => return None,
+    };
+    let end = HEADER_LEN + frame[3] as usize;
+    let payload = frame.get(HEADER_LEN..end)?;
+    let header = Header {
+        dst: frame[0],
+        src: frame[1],
+        kind,
+    };
+    Some((header, payload))
+}
+
+/// Receives frames until one is accepted by `filter`, or `timeout_us` elapses
+///
+/// The timeout covers the whole call, so a stream of frames rejected by `filter` can not
+/// keep the receiver waiting indefinitely.
+#[maybe_async::maybe_async]
+async fn receive_filtered<'a, SPI: SpiDevice, D: DelayNs, const N: usize>(
+    radio: &mut A7105<SPI>,
+    delay: &mut D,
+    frame: &'a mut [u8; N],
+    timeout_us: u32,
+    filter: impl Fn(&Header) -> bool,
+) -> Result<(Header, &'a [u8]), NetworkError<SPI::Error>> {
+    let mut remaining_us = timeout_us;
+    loop {
+        match radio.receive_within(frame, delay, &mut remaining_us).await {
+            Ok(()) => {}
+            Err(ReadPacketError::SpiError(e)) => return Err(e.into()),
+            Err(ReadPacketError::PacketError(_)) => continue,
+            Err(ReadPacketError::Timeout) => return Err(NetworkError::Timeout),
+        }
+
+        let accepted = match decode(frame) {
+            Some((header, payload)) if filter(&header) => Some((header, payload.len())),
+            _ => None,
+        };
+        if let Some((header, len)) = accepted {
+            return Ok((header, &frame[HEADER_LEN..HEADER_LEN + len]));
+        }
+    }
+}
+
+/// Returns `true` if `address` may be assigned to a [`Node`]
+const fn is_node_address(address: Address) -> bool {
+    address != HUB && address != BROADCAST
+}
+
+/// A frame sent by a [`Node`] to the [`Hub`] during its polling slot
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct Uplink {
+    /// The address of the node that sent the frame
+    pub src: Address,
+    /// The number of payload bytes written into the provided buffer
+    pub len: usize,
+}
+
+/// Configuration for a [`Hub`]
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct HubConfig {
+    /// How long a polled node has to respond, in microseconds
+    pub slot_us: u32,
+}
+
+impl Default for HubConfig {
+    fn default() -> Self {
+        Self { slot_us: 5_000 }
+    }
+}
+
+/// The central radio of a star network, coordinating up to `NODES` [`Node`]s with fixed
+/// `N` byte frames
+pub struct Hub<const N: usize, const NODES: usize> {
+    config: HubConfig,
+    nodes: [Option<Address>; NODES],
+    next_slot: usize,
+}
+
+impl<const N: usize, const NODES: usize> Hub<N, NODES> {
+    /// The largest payload that fits into a single frame
+    pub const MAX_PAYLOAD: usize = N - HEADER_LEN;
+
+    /// Constructs a new [`Hub`] without any registered nodes
+    pub const fn new(config: HubConfig) -> Self {
+        Self {
+            config,
+            nodes: [None; NODES],
+            next_slot: 0,
+        }
+    }
+
+    /// Registers a node, giving it a slot in the polling schedule
+    ///
+    /// Registering a node that is already registered has no effect.
+    pub fn add_node(&mut self, address: Address) -> Result<(), NetworkError<Infallible>> {
+        if !is_node_address(address) {
+            return Err(NetworkError::InvalidAddress);
+        }
+        if self.nodes.contains(&Some(address)) {
+            return Ok(());
+        }
+
+        let slot = self
+            .nodes
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(NetworkError::NodeTableFull)?;
+        *slot = Some(address);
+        Ok(())
+    }
+
+    /// Removes a node from the polling schedule
+    pub fn remove_node(&mut self, address: Address) {
+        for slot in self.nodes.iter_mut().filter(|slot| **slot == Some(address)) {
+            *slot = None;
+        }
+    }
+
+    /// Returns an iterator over the addresses of all registered nodes
+    pub fn nodes(&self) -> impl Iterator<Item = Address> + '_ {
+        self.nodes.iter().flatten().copied()
+    }
+
+    /// Returns the address of the node that will be polled next, advancing the schedule
+    fn next_node(&mut self) -> Option<Address> {
+        for _ in 0..NODES {
+            let slot = self.next_slot;
+            self.next_slot = (self.next_slot + 1) % NODES;
+            if let Some(address) = self.nodes[slot] {
+                return Some(address);
+            }
+        }
+        None
+    }
+
+    /// Sends a payload to a single node, or to every node if `dst` is [`BROADCAST`]
+    #[maybe_async::maybe_async]
+    pub async fn send<SPI: SpiDevice, D: DelayNs>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        dst: Address,
+        payload: &[u8],
+    ) -> Result<(), NetworkError<SPI::Error>> {
+        if dst == HUB {
+            return Err(NetworkError::InvalidAddress);
+        }
+
+        let header = Header {
+            dst,
+            src: HUB,
+            kind: FrameKind::Data,
+        };
+        let mut frame = [0; N];
+        encode(header, payload, &mut frame).ok_or(NetworkError::PayloadTooLarge)?;
+        radio.transmit(&frame, delay).await?;
+        Ok(())
+    }
+
+    /// Polls the next node in the schedule, giving it one slot to send a frame back
+    ///
+    /// Returns `None` if no nodes are registered, the node did not respond within its slot,
+    /// or it had nothing to send.
+    #[maybe_async::maybe_async]
+    pub async fn poll_next<SPI: SpiDevice, D: DelayNs>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        buf: &mut [u8],
+    ) -> Result<Option<Uplink>, NetworkError<SPI::Error>> {
+        let Some(node) = self.next_node() else {
+            return Ok(None);
+        };
+
+        let header = Header {
+            dst: node,
+            src: HUB,
+            kind: FrameKind::Poll,
+        };
+        let mut frame = [0; N];
+        encode(header, &[], &mut frame).ok_or(NetworkError::PayloadTooLarge)?;
+        radio.transmit(&frame, delay).await?;
+
+        let accept = |header: &Header| {
+            header.dst == HUB && header.src == node && header.kind == FrameKind::Data
+        };
+        let payload =
+            match receive_filtered(radio, delay, &mut frame, self.config.slot_us, accept).await {
+                Ok((_, payload)) => payload,
+                Err(NetworkError::Timeout) => return Ok(None),
+                Err(e) => return Err(e),
+            };
+        if payload.is_empty() {
+            return Ok(None);
+        }
+
+        let dest = buf
+            .get_mut(..payload.len())
+            .ok_or(NetworkError::BufferTooSmall)?;
+        dest.copy_from_slice(payload);
+        Ok(Some(Uplink {
+            src: node,
+            len: payload.len(),
+        }))
+    }
+}
+
+/// An event observed by a [`Node`] while listening to the network
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub enum NodeEvent {
+    /// A frame from the hub was received and its payload written into the provided buffer
+    Data {
+        /// The number of payload bytes written into the provided buffer
+        len: usize,
+        /// `true` if the frame was broadcast to every node
+        broadcast: bool,
+    },
+    /// The hub polled this node, which responded with the queued uplink payload, if any
+    Polled {
+        /// `true` if a queued payload was sent to the hub
+        sent: bool,
+    },
+}
+
+/// A radio in a star network with fixed `N` byte frames, only transmitting when polled
+/// by the [`Hub`]
+pub struct Node<const N: usize> {
+    address: Address,
+    pending: [u8; N],
+    pending_len: Option<usize>,
+}
+
+impl<const N: usize> Node<N> {
+    /// The largest payload that fits into a single frame
+    pub const MAX_PAYLOAD: usize = N - HEADER_LEN;
+
+    /// Constructs a new [`Node`] with the given address
+    ///
+    /// Neither [`HUB`] nor [`BROADCAST`] may be used as a node address.
+    pub fn new(address: Address) -> Result<Self, NetworkError<Infallible>> {
+        if !is_node_address(address) {
+            return Err(NetworkError::InvalidAddress);
+        }
+
+        Ok(Self {
+            address,
+            pending: [0; N],
+            pending_len: None,
+        })
+    }
+
+    /// Returns the address of this node
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Queues a payload to be sent to the hub the next time this node is polled, replacing
+    /// any payload that is already queued
+    pub fn queue(&mut self, payload: &[u8]) -> Result<(), NetworkError<Infallible>> {
+        let dest = self
+            .pending
+            .get_mut(..payload.len())
+            .filter(|_| payload.len() <= Self::MAX_PAYLOAD)
+            .ok_or(NetworkError::PayloadTooLarge)?;
+        dest.copy_from_slice(payload);
+        self.pending_len = Some(payload.len());
+        Ok(())
+    }
+
+    /// Returns `true` if this node should process a frame with the given header
+    fn accepts(&self, header: &Header) -> bool {
+        header.src == HUB && (header.dst == self.address || header.dst == BROADCAST)
+    }
+
+    /// Waits up to `timeout_us` microseconds for a frame from the hub addressed to this node
+    ///
+    /// Frames addressed to other nodes are ignored, without extending the wait. If this node
+    /// is polled, the queued uplink payload is sent to the hub, or an empty frame if nothing
+    /// is queued. The payload stays queued if it could not be sent.
+    #[maybe_async::maybe_async]
+    pub async fn listen<SPI: SpiDevice, D: DelayNs>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        buf: &mut [u8],
+        timeout_us: u32,
+    ) -> Result<NodeEvent, NetworkError<SPI::Error>> {
+        let mut frame = [0; N];
+        let accept = |header: &Header| {
+            self.accepts(header) && (header.kind == FrameKind::Data || header.dst == self.address)
+        };
+        let (header, payload) =
+            receive_filtered(radio, delay, &mut frame, timeout_us, accept).await?;
+
+        match header.kind {
+            FrameKind::Data => {
+                let dest = buf
+                    .get_mut(..payload.len())
+                    .ok_or(NetworkError::BufferTooSmall)?;
+                dest.copy_from_slice(payload);
+                Ok(NodeEvent::Data {
+                    len: payload.len(),
+                    broadcast: header.dst == BROADCAST,
+                })
+            }
+            FrameKind::Poll => {
+                let len = self.pending_len;
+                let header = Header {
+                    dst: HUB,
+                    src: self.address,
+                    kind: FrameKind::Data,
+                };
+                let payload = &self.pending[..len.unwrap_or(0)];
+                encode(header, payload, &mut frame).ok_or(NetworkError::PayloadTooLarge)?;
+                radio.transmit(&frame, delay).await?;
+                self.pending_len = None;
+                Ok(NodeEvent::Polled {
+                    sent: len.is_some(),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::*;
+    use crate::mock::{run, MockDelay, MockSpi};
+    use std::{boxed::Box, cell::RefCell, rc::Rc, vec::Vec};
+
+    #[test]
+    fn test_frame_round_trip() {
+        let header = Header {
+            dst: 0x02,
+            src: HUB,
+            kind: FrameKind::Data,
+        };
+        let mut frame = [0xFF; 8];
+        encode(header, &[1, 2], &mut frame).unwrap();
+        assert_eq!(frame, [0x02, HUB, KIND_DATA, 2, 1, 2, 0, 0]);
+        assert_eq!(decode(&frame), Some((header, &[1, 2][..])));
+
+        assert!(encode(header, &[0; 5], &mut frame).is_none());
+        assert_eq!(decode(&[0x02, HUB, 0x7F, 0]), None);
+    }
+
+    #[test]
+    fn test_node_filtering() {
+        let node: Node<8> = Node::new(0x02).unwrap();
+        let header = |dst, src| Header {
+            dst,
+            src,
+            kind: FrameKind::Data,
+        };
+        assert!(node.accepts(&header(0x02, HUB)));
+        assert!(node.accepts(&header(BROADCAST, HUB)));
+        assert!(!node.accepts(&header(0x03, HUB)));
+        assert!(!node.accepts(&header(0x02, 0x03)));
+
+        assert!(Node::<8>::new(HUB).is_err());
+        assert!(Node::<8>::new(BROADCAST).is_err());
+    }
+
+    #[test]
+    fn test_hub_schedule() {
+        let mut hub: Hub<8, 3> = Hub::new(HubConfig::default());
+        assert_eq!(hub.next_node(), None);
+
+        hub.add_node(0x01).unwrap();
+        hub.add_node(0x02).unwrap();
+        hub.add_node(0x02).unwrap();
+        assert_eq!(hub.add_node(BROADCAST), Err(NetworkError::InvalidAddress));
+
+        assert_eq!(hub.next_node(), Some(0x01));
+        assert_eq!(hub.next_node(), Some(0x02));
+        assert_eq!(hub.next_node(), Some(0x01));
+
+        hub.add_node(0x03).unwrap();
+        assert_eq!(hub.add_node(0x04), Err(NetworkError::NodeTableFull));
+
+        hub.remove_node(0x01);
+        assert_eq!(hub.nodes().count(), 2);
+        assert_eq!(hub.next_node(), Some(0x02));
+        assert_eq!(hub.next_node(), Some(0x03));
+        assert_eq!(hub.next_node(), Some(0x02));
+    }
+
+    #[test]
+    fn test_listen_timeout_spans_ignored_frames() {
+        let spi = MockSpi::new();
+        let mut frame = [0; 8];
+        let header = Header {
+            dst: 0x03,
+            src: HUB,
+            kind: FrameKind::Data,
+        };
+        encode(header, &[1], &mut frame).unwrap();
+        // Frames for another node keep arriving, but do not restart the timeout
+        spi.sim().airtime_polls = 5;
+        for _ in 0..100 {
+            spi.sim().push(&frame);
+        }
+        let mut radio = A7105::new(spi.clone());
+        let mut delay = MockDelay::default();
+        let mut node: Node<8> = Node::new(0x02).unwrap();
+
+        let mut buf = [0; 4];
+        let result = run!(node.listen(&mut radio, &mut delay, &mut buf, 1_000));
+        assert_eq!(result, Err(NetworkError::Timeout));
+        assert_eq!(delay.elapsed_us, 1_000);
+
+        // A broadcast is accepted after an ignored frame
+        let spi = MockSpi::new();
+        spi.sim().push(&frame);
+        let header = Header {
+            dst: BROADCAST,
+            ..header
+        };
+        encode(header, &[7, 8], &mut frame).unwrap();
+        spi.sim().push(&frame);
+        let mut radio = A7105::new(spi.clone());
+        let result = run!(node.listen(&mut radio, &mut delay, &mut buf, 1_000));
+        assert_eq!(
+            result,
+            Ok(NodeEvent::Data {
+                len: 2,
+                broadcast: true
+            })
+        );
+        assert_eq!(buf[..2], [7, 8]);
+    }
+
+    #[test]
+    fn test_poll_round_trip() {
+        // The node answers every frame the hub transmits from within the hub's simulated radio
+        let node_spi = MockSpi::new();
+        let mut node_radio = A7105::new(node_spi.clone());
+        let node = Rc::new(RefCell::new(Node::<8>::new(0x02).unwrap()));
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let hub_spi = MockSpi::new();
+        hub_spi.sim().responder = Some(Box::new({
+            let (node, events) = (node.clone(), events.clone());
+            move |frame| {
+                node_spi.sim().push(frame);
+                let mut buf = [0; 4];
+                let mut delay = MockDelay::default();
+                let mut node = node.borrow_mut();
+                let event = run!(node.listen(&mut node_radio, &mut delay, &mut buf, 1_000));
+                events.borrow_mut().push(event.unwrap());
+                node_spi.sim().sent.pop()
+            }
+        }));
+
+        let mut hub_radio = A7105::new(hub_spi.clone());
+        let mut delay = MockDelay::default();
+        let mut hub: Hub<8, 2> = Hub::new(HubConfig::default());
+        hub.add_node(0x02).unwrap();
+
+        node.borrow_mut().queue(&[5, 6, 7]).unwrap();
+        let mut buf = [0; 4];
+        let uplink = run!(hub.poll_next(&mut hub_radio, &mut delay, &mut buf)).unwrap();
+        assert_eq!(uplink, Some(Uplink { src: 0x02, len: 3 }));
+        assert_eq!(buf[..3], [5, 6, 7]);
+
+        // The queued payload was sent, so the next poll is answered with an empty frame
+        let uplink = run!(hub.poll_next(&mut hub_radio, &mut delay, &mut buf)).unwrap();
+        assert_eq!(uplink, None);
+        assert_eq!(
+            events.borrow()[..],
+            [
+                NodeEvent::Polled { sent: true },
+                NodeEvent::Polled { sent: false }
+            ]
+        );
+
+        run!(hub.send(&mut hub_radio, &mut delay, 0x02, &[9])).unwrap();
+        assert_eq!(
+            events.borrow().last(),
+            Some(&NodeEvent::Data {
+                len: 1,
+                broadcast: false
+            })
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! Power management and energy accounting
+//!
+//! A [`PowerManager`] wraps the mode changes of an [`A7105`], waiting out the crystal and
+//! PLL settling times configured through [`Delay2`] and [`Delay1`] when waking the radio
+//! up, and recording how long the radio spends in each [`Mode`]. Those durations can then be
+//! turned into an [`EnergyReport`] using the typical supply currents from the datasheet, or
+//! a [`CurrentProfile`] measured on the actual board.
+//!
+//! ```ignore
+//! use a7105::power::{CurrentProfile, LowPowerLevel, PowerManager};
+//! use a7105::prelude::*;
+//!
+//! # let (a7105_spi_peripheral, mut delay, clock) = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//! let mut power = PowerManager::new(clock, Default::default(), Default::default());
+//!
+//! power.wake(&mut radio, &mut delay, Mode::Rx).await.unwrap();
+//! // ...
+//! power.enter_low_power(&mut radio, LowPowerLevel::Sleep).await.unwrap();
+//!
+//! let report = power.durations().energy(&CurrentProfile::default());
+//! ```
+
+use crate::{
+    commands::Mode,
+    registers::{Delay1, Delay2, TxPower},
+    time::Monotonic,
+    A7105,
+};
+use defmt::Format;
+
+#[cfg(feature = "blocking")]
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs, spi::SpiDevice};
+
+const MODES: [Mode; 6] = [
+    Mode::Sleep,
+    Mode::Idle,
+    Mode::Standby,
+    Mode::Pll,
+    Mode::Rx,
+    Mode::Tx,
+];
+
+fn index(mode: Mode) -> usize {
+    match mode {
+        Mode::Sleep => 0,
+        Mode::Idle => 1,
+        Mode::Standby => 2,
+        Mode::Pll => 3,
+        Mode::Rx => 4,
+        Mode::Tx => 5,
+    }
+}
+
+/// The low power modes a [`PowerManager`] can place the A7105 in
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub enum LowPowerLevel {
+    /// [`Mode::Idle`], which keeps the regulator on and the FIFO accessible
+    Idle,
+    /// [`Mode::Sleep`], which turns off everything but the SPI interface
+    Sleep,
+}
+
+/// The time spent in each [`Mode`], in microseconds
+#[derive(Format, PartialEq, Debug, Copy, Clone, Default)]
+pub struct ModeDurations {
+    durations_us: [u64; 6],
+}
+
+impl ModeDurations {
+    /// Returns the time spent in the given mode, in microseconds
+    pub fn get(&self, mode: Mode) -> u64 {
+        self.durations_us[index(mode)]
+    }
+
+    /// Returns the total time accounted for, in microseconds
+    pub fn total_us(&self) -> u64 {
+        self.durations_us.iter().sum()
+    }
+
+    /// Estimates the charge drawn over these durations with the given current profile
+    pub fn energy(&self, profile: &CurrentProfile) -> EnergyReport {
+        let mut charge_nc = [0; 6];
+        for mode in MODES {
+            let i = index(mode);
+            // Widened, as months of receive time at 16mA would overflow a u64 in nA·µs
+            let charge = u128::from(profile.get(mode)) * u128::from(self.durations_us[i]);
+            charge_nc[i] = (charge / 1_000_000) as u64;
+        }
+
+        EnergyReport {
+            charge_nc,
+            duration_us: self.total_us(),
+        }
+    }
+}
+
+/// Records the time the A7105 spends in each [`Mode`]
+///
+/// The tracker only knows about the transitions it is told about. In FIFO mode the A7105
+/// leaves [`Mode::Rx`] and [`Mode::Tx`] on its own once a packet is complete, which should
+/// be reported through [`ModeTracker::transition`] for the durations to be accurate.
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct ModeTracker {
+    mode: Mode,
+    since_us: u64,
+    durations: ModeDurations,
+}
+
+impl ModeTracker {
+    /// Constructs a new [`ModeTracker`] for a radio that entered `mode` at `now_us`
+    pub const fn new(mode: Mode, now_us: u64) -> Self {
+        Self {
+            mode,
+            since_us: now_us,
+            durations: ModeDurations {
+                durations_us: [0; 6],
+            },
+        }
+    }
+
+    /// Returns the mode the radio is currently in
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Records that the radio entered `mode` at `now_us`
+    pub fn transition(&mut self, mode: Mode, now_us: u64) {
+        self.durations.durations_us[index(self.mode)] += now_us.saturating_sub(self.since_us);
+        self.mode = mode;
+        self.since_us = now_us.max(self.since_us);
+    }
+
+    /// Returns the time spent in each mode up until `now_us`
+    pub fn durations(&self, now_us: u64) -> ModeDurations {
+        let mut durations = self.durations;
+        durations.durations_us[index(self.mode)] += now_us.saturating_sub(self.since_us);
+        durations
+    }
+
+    /// Clears all recorded durations, keeping the current mode
+    pub fn reset(&mut self, now_us: u64) {
+        *self = Self::new(self.mode, now_us);
+    }
+}
+
+/// The supply current drawn in each [`Mode`], in nA
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct CurrentProfile {
+    currents_na: [u64; 6],
+}
+
+impl CurrentProfile {
+    /// Constructs a profile from the current drawn in each mode, in nA
+    pub const fn new(sleep: u64, idle: u64, standby: u64, pll: u64, rx: u64, tx: u64) -> Self {
+        Self {
+            currents_na: [sleep, idle, standby, pll, rx, tx],
+        }
+    }
+
+    /// Returns the current drawn in the given mode, in nA
+    pub fn get(&self, mode: Mode) -> u64 {
+        self.currents_na[index(mode)]
+    }
+
+    /// Replaces the TX current with the typical current of the given transmit power level
+    pub fn with_tx_power(mut self, level: TxPower) -> Self {
+        self.currents_na[index(Mode::Tx)] = u64::from(level.current_ua) * 1_000;
+        self
+    }
+}
+
+impl Default for CurrentProfile {
+    /// The typical currents listed in the datasheet, transmitting at 0dBm
+    fn default() -> Self {
+        Self::new(1_500, 300_000, 1_900_000, 9_000_000, 16_000_000, 20_000_000)
+    }
+}
+
+/// An estimate of the charge drawn by the A7105
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct EnergyReport {
+    charge_nc: [u64; 6],
+    duration_us: u64,
+}
+
+impl EnergyReport {
+    /// Returns the charge drawn in the given mode, in nC
+    pub fn charge_nc(&self, mode: Mode) -> u64 {
+        self.charge_nc[index(mode)]
+    }
+
+    /// Returns the total charge drawn, in nC
+    pub fn total_charge_nc(&self) -> u64 {
+        self.charge_nc.iter().sum()
+    }
+
+    /// Returns the total energy drawn from a supply of the given voltage, in µJ
+    pub fn energy_uj(&self, supply_mv: u32) -> u64 {
+        (u128::from(self.total_charge_nc()) * u128::from(supply_mv) / 1_000_000) as u64
+    }
+
+    /// Returns the average current over the reported period, in nA
+    pub fn average_current_na(&self) -> u64 {
+        match self.duration_us {
+            0 => 0,
+            duration_us => {
+                (u128::from(self.total_charge_nc()) * 1_000_000 / u128::from(duration_us)) as u64
+            }
+        }
+    }
+}
+
+/// Sequences mode changes of the A7105 while tracking the time spent in each mode
+///
+/// Refer to the [module level documentation](self) for an overview.
+pub struct PowerManager<C> {
+    clock: C,
+    delay1: Delay1,
+    delay2: Delay2,
+    tracker: ModeTracker,
+}
+
+impl<C: Monotonic> PowerManager<C> {
+    /// Constructs a new [`PowerManager`] for a radio currently in [`Mode::Standby`]
+    ///
+    /// `delay1` and `delay2` must match the values written to the A7105.
+    pub fn new(clock: C, delay1: Delay1, delay2: Delay2) -> Self {
+        let tracker = ModeTracker::new(Mode::Standby, clock.now_us());
+        Self {
+            clock,
+            delay1,
+            delay2,
+            tracker,
+        }
+    }
+
+    /// Returns the mode tracker
+    pub fn tracker(&self) -> &ModeTracker {
+        &self.tracker
+    }
+
+    /// Returns the mode tracker, for example to report a transition made elsewhere
+    pub fn tracker_mut(&mut self) -> &mut ModeTracker {
+        &mut self.tracker
+    }
+
+    /// Returns the time spent in each mode so far
+    pub fn durations(&self) -> ModeDurations {
+        self.tracker.durations(self.clock.now_us())
+    }
+
+    /// Places the A7105 in the given mode, recording the transition
+    #[maybe_async::maybe_async]
+    pub async fn set_mode<SPI: SpiDevice>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        mode: Mode,
+    ) -> Result<(), SPI::Error> {
+        radio.set_mode(mode).await?;
+        self.tracker.transition(mode, self.clock.now_us());
+        Ok(())
+    }
+
+    /// Places the A7105 in the given low power mode
+    #[maybe_async::maybe_async]
+    pub async fn enter_low_power<SPI: SpiDevice>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        level: LowPowerLevel,
+    ) -> Result<(), SPI::Error> {
+        let mode = match level {
+            LowPowerLevel::Idle => Mode::Idle,
+            LowPowerLevel::Sleep => Mode::Sleep,
+        };
+        self.set_mode(radio, mode).await
+    }
+
+    /// Wakes the A7105 into the given mode, returning once it is ready for use
+    ///
+    /// When coming out of a low power mode the crystal is started by moving through
+    /// [`Mode::Standby`] and its settling delay is waited out. When the target mode needs
+    /// the PLL, the PLL settling delays are waited out as well.
+    #[maybe_async::maybe_async]
+    pub async fn wake<SPI: SpiDevice, D: DelayNs>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        mode: Mode,
+    ) -> Result<(), SPI::Error> {
+        if matches!(self.tracker.mode(), Mode::Sleep | Mode::Idle) {
+            self.set_mode(radio, Mode::Standby).await?;
+            delay.delay_us(self.delay2.xtal_settling_delay.us()).await;
+        }
+
+        let settle_us = match mode {
+            Mode::Sleep | Mode::Idle | Mode::Standby => 0,
+            Mode::Pll | Mode::Rx => self.delay1.pll_to_wpll.us(),
+            Mode::Tx => self.delay1.pll_to_wpll.us() + self.delay1.wpl_to_tx.us(),
+        };
+        self.set_mode(radio, mode).await?;
+        if settle_us > 0 {
+            delay.delay_us(settle_us).await;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::{run, MockDelay, MockSpi};
+
+    struct MockClock(u64);
+
+    impl Monotonic for MockClock {
+        fn now_us(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_mode_tracker() {
+        let mut tracker = ModeTracker::new(Mode::Standby, 1_000);
+        tracker.transition(Mode::Rx, 3_000);
+        tracker.transition(Mode::Sleep, 4_000);
+
+        let durations = tracker.durations(10_000);
+        assert_eq!(durations.get(Mode::Standby), 2_000);
+        assert_eq!(durations.get(Mode::Rx), 1_000);
+        assert_eq!(durations.get(Mode::Sleep), 6_000);
+        assert_eq!(durations.get(Mode::Tx), 0);
+        assert_eq!(durations.total_us(), 9_000);
+
+        tracker.reset(10_000);
+        assert_eq!(tracker.mode(), Mode::Sleep);
+        assert_eq!(tracker.durations(10_500).total_us(), 500);
+    }
+
+    #[test]
+    fn test_energy_report() {
+        let mut tracker = ModeTracker::new(Mode::Rx, 0);
+        tracker.transition(Mode::Sleep, 1_000);
+        let report = tracker
+            .durations(1_000_000)
+            .energy(&CurrentProfile::default());
+
+        // 1ms at 16mA and 999ms at 1.5µA
+        assert_eq!(report.charge_nc(Mode::Rx), 16_000);
+        assert_eq!(report.charge_nc(Mode::Sleep), 1_498);
+        assert_eq!(report.total_charge_nc(), 17_498);
+        assert_eq!(report.average_current_na(), 17_498);
+        assert_eq!(report.energy_uj(3_000), 52);
+
+        let profile = CurrentProfile::default().with_tx_power(TxPower::nearest(-20));
+        assert_eq!(profile.get(Mode::Tx), 13_400_000);
+    }
+
+    #[test]
+    fn test_energy_report_long_durations() {
+        // A year of receiving at 16mA
+        let year_us = 365 * 24 * 3_600 * 1_000_000;
+        let report = ModeTracker::new(Mode::Rx, 0)
+            .durations(year_us)
+            .energy(&CurrentProfile::default());
+        assert_eq!(report.charge_nc(Mode::Rx), 16_000_000 * 365 * 24 * 3_600);
+        assert_eq!(report.average_current_na(), 16_000_000);
+        assert_eq!(report.energy_uj(3_000), 1_513_728_000_000);
+    }
+
+    #[test]
+    fn test_wake() {
+        let spi = MockSpi::new();
+        let mut radio = A7105::new(spi.clone());
+        let mut delay = MockDelay::default();
+        let (delay1, delay2) = (Delay1::default(), Delay2::default());
+        let mut power = PowerManager::new(MockClock(0), delay1, delay2);
+
+        run!(power.enter_low_power(&mut radio, LowPowerLevel::Sleep)).unwrap();
+        assert_eq!(power.tracker().mode(), Mode::Sleep);
+
+        // The crystal settles in standby before the PLL settles for RX
+        run!(power.wake(&mut radio, &mut delay, Mode::Rx)).unwrap();
+        let strobes: [&[u8]; 3] = [&[0x80], &[0xA0], &[0xC0]];
+        assert_eq!(spi.sim().transactions[..], strobes);
+        let xtal_us = u64::from(delay2.xtal_settling_delay.us());
+        let pll_us = u64::from(delay1.pll_to_wpll.us());
+        assert_eq!(delay.elapsed_us, xtal_us + pll_us);
+        assert_eq!(power.tracker().mode(), Mode::Rx);
+
+        // The crystal is already running, so only the PLL and TX settling delays are waited
+        delay.elapsed_us = 0;
+        run!(power.wake(&mut radio, &mut delay, Mode::Tx)).unwrap();
+        assert_eq!(spi.sim().transactions.len(), 4);
+        assert_eq!(spi.sim().transactions[3][..], [0xD0]);
+        let tx_us = u64::from(delay1.wpl_to_tx.us());
+        assert_eq!(delay.elapsed_us, pll_us + tx_us);
+
+        delay.elapsed_us = 0;
+        run!(power.wake(&mut radio, &mut delay, Mode::Standby)).unwrap();
+        assert_eq!(delay.elapsed_us, 0);
+        assert_eq!(power.tracker().mode(), Mode::Standby);
+    }
+}
@@ -1,4 +1,11 @@
 pub use crate::commands::{Command, Mode};
-pub use crate::error::{PacketError, ReadPacketError};
+#[cfg(feature = "secure")]
+pub use crate::error::SecureError;
+#[cfg(feature = "stream")]
+pub use crate::error::StreamError;
+pub use crate::error::{
+    BindError, ConfigError, LinkError, ManagerError, NetworkError, PacketError, ReadPacketError,
+    TdmaError, ThreeWireError, VerifyError, WakeError, WriteError,
+};
 pub use crate::registers;
 pub use crate::A7105;
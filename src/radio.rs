@@ -0,0 +1,271 @@
+//! Implementations of the [`radio`](https://crates.io/crates/radio) crate traits
+//!
+//! The `radio` crate defines common abstractions over packet radios, allowing protocol code
+//! and test harnesses to be shared between the A7105 and other transceivers. Its traits are
+//! blocking, so this module is only built alongside the `blocking` feature, enabled with
+//! `--no-default-features --features blocking,radio`. The `radio` feature on its own
+//! builds nothing.
+//!
+//! Packets are always a fixed length, matching the FIFO length configured through
+//! [`Fifo1`](crate::registers::Fifo1), so [`Receive::get_received`] fills the whole of the
+//! provided buffer. [`State::get_state`] can not tell [`Mode::Sleep`] and [`Mode::Idle`]
+//! apart, as neither runs the crystal oscillator, and reports both as [`Mode::Idle`].
+//!
+//! ```ignore
+//! use a7105::prelude::*;
+//! use radio::{Channel, Receive, Transmit};
+//!
+//! # let a7105_spi_peripheral = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//!
+//! Channel::set_channel(&mut radio, &42).unwrap();
+//! radio.start_transmit(&[1, 2, 3, 4]).unwrap();
+//! while !radio.check_transmit().unwrap() {}
+//!
+//! radio.start_receive().unwrap();
+//! while !radio.check_receive(true).unwrap() {}
+//! let mut buf = [0; 4];
+//! let (len, info) = radio.get_received(&mut buf).unwrap();
+//! ```
+
+use crate::{
+    commands::Mode,
+    registers::{self, Pll1, RssiAdcOutput},
+    ReadPacketError, A7105,
+};
+use ::radio::{BasicInfo, Busy, Channel, Power, RadioState, Receive, Rssi, State, Transmit};
+use embedded_hal::spi::SpiDevice;
+
+/// The ends of the datasheet's RSSI range as `(ADC reading, dBm)`, a rough reading of the
+/// typical RSSI characteristic in figure 17.1
+const RSSI_WEAK: (i16, i16) = (150, -105);
+const RSSI_STRONG: (i16, i16) = (40, -50);
+
+/// Converts an RSSI reading to an approximate input power in dBm
+///
+/// The datasheet only quotes an accuracy of ±6dBm, and readings outside of its RSSI range
+/// are clamped to the range.
+fn rssi_dbm(rssi: RssiAdcOutput) -> i16 {
+    let adc = (rssi.voltage * 256. / 1.2 + 0.5) as i16;
+    let adc = adc.clamp(RSSI_STRONG.0, RSSI_WEAK.0);
+    RSSI_WEAK.1
+        + (RSSI_WEAK.0 - adc) * (RSSI_STRONG.1 - RSSI_WEAK.1) / (RSSI_WEAK.0 - RSSI_STRONG.0)
+}
+
+impl RadioState for Mode {
+    fn idle() -> Self {
+        Mode::Idle
+    }
+
+    fn sleep() -> Self {
+        Mode::Sleep
+    }
+}
+
+impl<SPI: SpiDevice> Transmit for A7105<SPI> {
+    type Error = SPI::Error;
+
+    fn start_transmit(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.tx(data)?;
+        self.set_mode(Mode::Tx)
+    }
+
+    fn check_transmit(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.is_busy()?)
+    }
+}
+
+impl<SPI: SpiDevice> Receive for A7105<SPI> {
+    type Error = ReadPacketError<SPI::Error>;
+    type Info = BasicInfo;
+
+    fn start_receive(&mut self) -> Result<(), Self::Error> {
+        Ok(self.set_mode(Mode::Rx)?)
+    }
+
+    /// Returns `true` once a valid packet has been received
+    ///
+    /// Must only be called after [`Receive::start_receive`]. A packet failing its CRC or FEC
+    /// checks either restarts the receiver, if `restart` is set, or returns
+    /// [`ReadPacketError::PacketError`].
+    fn check_receive(&mut self, restart: bool) -> Result<bool, Self::Error> {
+        let mode: registers::Mode = self.read_reg()?;
+        if mode.trx_enabled {
+            return Ok(false);
+        }
+        if mode.crc_pass && mode.fec_pass {
+            return Ok(true);
+        }
+        if restart {
+            self.start_receive()?;
+            Ok(false)
+        } else {
+            Err(ReadPacketError::PacketError(mode.into()))
+        }
+    }
+
+    fn get_received(&mut self, buff: &mut [u8]) -> Result<(usize, Self::Info), Self::Error> {
+        self.rx(buff)?;
+        let rssi: RssiAdcOutput = self.read_reg()?;
+        Ok((buff.len(), BasicInfo::new(rssi_dbm(rssi), 0)))
+    }
+}
+
+impl<SPI: SpiDevice> Rssi for A7105<SPI> {
+    type Error = SPI::Error;
+
+    /// Returns the approximate input power in dBm, which the A7105 only measures while in
+    /// [`Mode::Rx`]
+    fn poll_rssi(&mut self) -> Result<i16, Self::Error> {
+        let rssi: RssiAdcOutput = self.read_reg()?;
+        Ok(rssi_dbm(rssi))
+    }
+}
+
+impl<SPI: SpiDevice> Channel for A7105<SPI> {
+    type Channel = u8;
+    type Error = SPI::Error;
+
+    fn set_channel(&mut self, channel: &u8) -> Result<(), Self::Error> {
+        self.write_reg(Pll1 { channel: *channel })
+    }
+}
+
+impl<SPI: SpiDevice> Power for A7105<SPI> {
+    type Error = SPI::Error;
+
+    /// Sets the transmit power to the datasheet level closest to `power` dBm
+    fn set_power(&mut self, power: i8) -> Result<(), Self::Error> {
+        self.set_tx_power(power).map(|_| ())
+    }
+}
+
+impl<SPI: SpiDevice> State for A7105<SPI> {
+    type State = Mode;
+    type Error = SPI::Error;
+
+    fn set_state(&mut self, state: Mode) -> Result<(), Self::Error> {
+        self.set_mode(state)
+    }
+
+    fn get_state(&mut self) -> Result<Mode, Self::Error> {
+        let mode: registers::Mode = self.read_reg()?;
+        Ok(if mode.trx_enabled {
+            match mode.trx_status {
+                registers::TrxStatus::Rx => Mode::Rx,
+                registers::TrxStatus::Tx => Mode::Tx,
+            }
+        } else if mode.pll_enabled {
+            Mode::Pll
+        } else if mode.internal_crystal_enabled {
+            Mode::Standby
+        } else {
+            Mode::Idle
+        })
+    }
+}
+
+impl<SPI: SpiDevice> Busy for A7105<SPI> {
+    type Error = SPI::Error;
+
+    fn is_busy(&mut self) -> Result<bool, Self::Error> {
+        A7105::is_busy(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{mock::MockSpi, registers::Register, PacketError};
+    use ::radio::ReceiveInfo;
+
+    fn reading(adc: u8) -> RssiAdcOutput {
+        RssiAdcOutput::from(adc)
+    }
+
+    #[test]
+    fn test_rssi_dbm() {
+        assert_eq!(rssi_dbm(reading(150)), -105);
+        assert_eq!(rssi_dbm(reading(40)), -50);
+        assert_eq!(rssi_dbm(reading(95)), -78);
+        // Readings outside of the datasheet range are clamped
+        assert_eq!(rssi_dbm(reading(255)), -105);
+        assert_eq!(rssi_dbm(reading(0)), -50);
+    }
+
+    #[test]
+    fn test_transmit() {
+        let spi = MockSpi::new();
+        spi.sim().tx_polls = 2;
+        let mut radio = A7105::new(spi.clone());
+
+        radio.start_transmit(&[1, 2, 3]).unwrap();
+        assert_eq!(spi.sim().sent[..], [[1, 2, 3]]);
+        assert!(!radio.check_transmit().unwrap());
+        assert!(!radio.check_transmit().unwrap());
+        assert!(radio.check_transmit().unwrap());
+    }
+
+    #[test]
+    fn test_receive() {
+        let spi = MockSpi::new();
+        spi.sim().airtime_polls = 1;
+        spi.sim().push_corrupt(&[9, 9]);
+        spi.sim().push(&[1, 2]);
+        spi.sim().set_reg(RssiAdcOutput::id(), 95);
+        let mut radio = A7105::new(spi.clone());
+
+        radio.start_receive().unwrap();
+        assert!(!radio.check_receive(true).unwrap());
+        // The corrupt packet restarts the receiver, which then hears the valid one
+        assert!(!radio.check_receive(true).unwrap());
+        assert!(spi.sim().in_rx());
+        assert!(!radio.check_receive(true).unwrap());
+        assert!(radio.check_receive(true).unwrap());
+
+        let mut buf = [0; 2];
+        let (len, info) = radio.get_received(&mut buf).unwrap();
+        assert_eq!((len, buf), (2, [1, 2]));
+        assert_eq!(info.rssi(), -78);
+    }
+
+    #[test]
+    fn test_receive_error() {
+        let spi = MockSpi::new();
+        spi.sim().push_corrupt(&[9, 9]);
+        let mut radio = A7105::new(spi.clone());
+
+        radio.start_receive().unwrap();
+        assert_eq!(
+            radio.check_receive(false),
+            Err(ReadPacketError::PacketError(PacketError {
+                fec_failed: false,
+                crc_failed: true,
+            }))
+        );
+        assert!(!spi.sim().in_rx());
+    }
+
+    #[test]
+    fn test_get_state() {
+        let spi = MockSpi::new();
+        let mut radio = A7105::new(spi.clone());
+
+        for (state, expected) in [
+            (Mode::Sleep, Mode::Idle),
+            (Mode::Idle, Mode::Idle),
+            (Mode::Standby, Mode::Standby),
+            (Mode::Pll, Mode::Pll),
+            (Mode::Rx, Mode::Rx),
+        ] {
+            radio.set_state(state).unwrap();
+            assert_eq!(radio.get_state().unwrap(), expected);
+        }
+
+        spi.sim().tx_polls = 1;
+        radio.start_transmit(&[1]).unwrap();
+        assert_eq!(radio.get_state().unwrap(), Mode::Tx);
+        // The A7105 stays in PLL mode once the transmission completes
+        assert_eq!(radio.get_state().unwrap(), Mode::Pll);
+    }
+}
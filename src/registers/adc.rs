@@ -39,7 +39,15 @@ impl Register for AdcControl {
     }
 }
 
-impl WritableRegister for AdcControl {}
+impl WritableRegister for AdcControl {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.adc_control)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.adc_control)
+    }
+}
 
 impl From<AdcControl> for u8 {
     fn from(val: AdcControl) -> Self {
@@ -19,7 +19,15 @@ impl Register for BatteryDetectConfig {
     }
 }
 
-impl WritableRegister for BatteryDetectConfig {}
+impl WritableRegister for BatteryDetectConfig {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.battery_detect_config)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.battery_detect_config)
+    }
+}
 
 impl From<BatteryDetectConfig> for u8 {
     fn from(val: BatteryDetectConfig) -> Self {
@@ -90,6 +98,34 @@ pub enum DetectThreshold {
     V27,
 }
 
+impl DetectThreshold {
+    /// Every threshold, in increasing order of voltage
+    pub const ALL: [DetectThreshold; 8] = [
+        Self::V20,
+        Self::V21,
+        Self::V22,
+        Self::V23,
+        Self::V24,
+        Self::V25,
+        Self::V26,
+        Self::V27,
+    ];
+
+    /// Returns the threshold voltage in millivolts
+    pub fn millivolts(&self) -> u16 {
+        match self {
+            Self::V20 => 2000,
+            Self::V21 => 2100,
+            Self::V22 => 2200,
+            Self::V23 => 2300,
+            Self::V24 => 2400,
+            Self::V25 => 2500,
+            Self::V26 => 2600,
+            Self::V27 => 2700,
+        }
+    }
+}
+
 #[derive(Format, PartialEq, Debug, Copy, Clone, Default)]
 pub struct BatteryDetectResult {
     /// Battery detection flag
@@ -125,4 +161,10 @@ mod test {
         assert_eq!(BatteryDetectConfig::id(), 0x27);
         assert_eq!(BatteryDetectResult::id(), 0x27);
     }
+
+    #[test]
+    fn test_detect_thresholds() {
+        let millivolts = DetectThreshold::ALL.map(|threshold| threshold.millivolts());
+        assert_eq!(millivolts, [2000, 2100, 2200, 2300, 2400, 2500, 2600, 2700]);
+    }
 }
@@ -18,7 +18,12 @@ impl Register for CalibrationControl {
 }
 
 impl ReadableRegister for CalibrationControl {}
-impl WritableRegister for CalibrationControl {}
+impl WritableRegister for CalibrationControl {
+    fn readback_mask() -> Option<[u8; 1]> {
+        // Every calibration enable bit clears automatically once the calibration is done
+        Some([0x00])
+    }
+}
 
 impl From<u8> for CalibrationControl {
     fn from(val: u8) -> Self {
@@ -39,7 +39,15 @@ impl Register for CkoPinControl {
     }
 }
 
-impl WritableRegister for CkoPinControl {}
+impl WritableRegister for CkoPinControl {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.cko_pin_control)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.cko_pin_control)
+    }
+}
 
 impl From<CkoPinControl> for u8 {
     fn from(val: CkoPinControl) -> Self {
@@ -37,6 +37,28 @@ impl Default for Clock {
     }
 }
 
+impl Clock {
+    /// Returns the master clock frequency, F_MCLK, for the given crystal frequency and PLL
+    /// configuration
+    pub fn master_clock_hz(&self, xtal_hz: u32, pll2: &Pll2) -> u32 {
+        if self.clock_generated_enabled {
+            32_000_000
+        } else {
+            pll2.reference_hz(xtal_hz)
+        }
+    }
+
+    /// Returns the system clock frequency, F_SYCK, that drives the data rate and ADC clocks
+    pub fn system_clock_hz(&self, xtal_hz: u32, pll2: &Pll2) -> u32 {
+        self.master_clock_hz(xtal_hz, pll2)
+            / match self.sys_clock_div {
+                SystemClockDiv::Div1 => 1,
+                SystemClockDiv::Div2 => 2,
+                SystemClockDiv::Div4 => 4,
+            }
+    }
+}
+
 impl Register for Clock {
     fn id() -> u8 {
         0x0D
@@ -44,7 +66,19 @@ impl Register for Clock {
 }
 
 impl ReadableRegister for Clock {}
-impl WritableRegister for Clock {}
+impl WritableRegister for Clock {
+    fn readback_mask() -> Option<[u8; 1]> {
+        Some([0xFF])
+    }
+
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.clock)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.clock)
+    }
+}
 
 impl From<u8> for Clock {
     fn from(val: u8) -> Self {
@@ -82,4 +116,20 @@ mod test {
 
         assert_eq!(Clock::id(), 0xD);
     }
+
+    #[test]
+    fn test_system_clock() {
+        let pll2 = Pll2::default();
+        assert_eq!(
+            Clock::default().system_clock_hz(16_000_000, &pll2),
+            16_000_000
+        );
+
+        let clock = Clock {
+            clock_generated_enabled: true,
+            sys_clock_div: SystemClockDiv::Div4,
+            ..Default::default()
+        };
+        assert_eq!(clock.system_clock_hz(12_000_000, &pll2), 8_000_000);
+    }
 }
@@ -32,7 +32,15 @@ impl Register for Code1 {
     }
 }
 
-impl WritableRegister for Code1 {}
+impl WritableRegister for Code1 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.code1)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.code1)
+    }
+}
 
 impl From<Code1> for u8 {
     fn from(val: Code1) -> u8 {
@@ -97,7 +105,15 @@ impl Register for Code2 {
     }
 }
 
-impl WritableRegister for Code2 {}
+impl WritableRegister for Code2 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.code2)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.code2)
+    }
+}
 
 impl From<Code2> for u8 {
     fn from(val: Code2) -> u8 {
@@ -130,7 +146,15 @@ impl Default for Code3 {
     }
 }
 
-impl WritableRegister for Code3 {}
+impl WritableRegister for Code3 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.code3)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.code3)
+    }
+}
 
 impl Register for Code3 {
     fn id() -> u8 {
@@ -6,6 +6,26 @@ pub struct DataRate {
     pub rate: u8,
 }
 
+impl DataRate {
+    /// Computes the divider that produces exactly `bps` from the system clock, returning
+    /// `None` if the rate cannot be reached
+    pub fn from_bps(system_clock_hz: u32, bps: u32) -> Option<Self> {
+        let base = system_clock_hz / 32;
+        if bps == 0 || system_clock_hz % 32 != 0 || base % bps != 0 {
+            return None;
+        }
+
+        let rate = u8::try_from(base / bps - 1).ok()?;
+        Some(Self { rate })
+    }
+
+    /// Returns the data rate produced by this divider from the system clock, in bits per
+    /// second
+    pub fn bps(&self, system_clock_hz: u32) -> u32 {
+        system_clock_hz / 32 / (u32::from(self.rate) + 1)
+    }
+}
+
 impl Register for DataRate {
     fn id() -> u8 {
         0x0E
@@ -13,7 +33,19 @@ impl Register for DataRate {
 }
 
 impl ReadableRegister for DataRate {}
-impl WritableRegister for DataRate {}
+impl WritableRegister for DataRate {
+    fn readback_mask() -> Option<[u8; 1]> {
+        Some([0xFF])
+    }
+
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.data_rate)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.data_rate)
+    }
+}
 
 impl From<u8> for DataRate {
     fn from(rate: u8) -> Self {
@@ -39,4 +71,27 @@ mod test {
 
         assert_eq!(DataRate::id(), 0xE);
     }
+
+    #[test]
+    fn test_data_rate_bps() {
+        // The dividers listed in the datasheet for a 16MHz system clock
+        for (bps, rate) in [
+            (500_000, 0x00),
+            (250_000, 0x01),
+            (125_000, 0x03),
+            (100_000, 0x04),
+            (50_000, 0x09),
+            (25_000, 0x13),
+            (10_000, 0x31),
+            (2_000, 0xF9),
+        ] {
+            assert_eq!(DataRate::from_bps(16_000_000, bps), Some(DataRate { rate }));
+            assert_eq!(DataRate { rate }.bps(16_000_000), bps);
+        }
+
+        assert_eq!(DataRate::from_bps(16_000_000, 1_000_000), None);
+        assert_eq!(DataRate::from_bps(16_000_000, 300_000), None);
+        assert_eq!(DataRate::from_bps(16_000_000, 1_000), None);
+        assert_eq!(DataRate::from_bps(16_000_000, 0), None);
+    }
 }
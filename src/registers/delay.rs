@@ -9,6 +9,18 @@ pub enum WpllToTx {
     Us80,
 }
 
+impl WpllToTx {
+    /// Returns the delay in microseconds
+    pub fn us(&self) -> u32 {
+        match self {
+            Self::Us20 => 20,
+            Self::Us40 => 40,
+            Self::Us60 => 60,
+            Self::Us80 => 80,
+        }
+    }
+}
+
 #[derive(Format, PartialEq, Debug, Copy, Clone)]
 pub enum PllToWpll {
     Us50,
@@ -17,6 +29,18 @@ pub enum PllToWpll {
     Us110,
 }
 
+impl PllToWpll {
+    /// Returns the delay in microseconds
+    pub fn us(&self) -> u32 {
+        match self {
+            Self::Us50 => 50,
+            Self::Us70 => 70,
+            Self::Us90 => 90,
+            Self::Us110 => 110,
+        }
+    }
+}
+
 #[derive(Format, PartialEq, Debug, Copy, Clone)]
 pub struct Delay1 {
     pub wpl_to_tx: WpllToTx,
@@ -38,7 +62,15 @@ impl Register for Delay1 {
     }
 }
 
-impl WritableRegister for Delay1 {}
+impl WritableRegister for Delay1 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.delay1)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.delay1)
+    }
+}
 
 impl From<Delay1> for u8 {
     fn from(val: Delay1) -> u8 {
@@ -69,6 +101,22 @@ pub enum XtalSettlingDelay {
     Us2500,
 }
 
+impl XtalSettlingDelay {
+    /// Returns the delay in microseconds
+    pub fn us(&self) -> u32 {
+        match self {
+            Self::Us200 => 200,
+            Self::Us400 => 400,
+            Self::Us600 => 600,
+            Self::Us800 => 800,
+            Self::Us1000 => 1000,
+            Self::Us1500 => 1500,
+            Self::Us2000 => 2000,
+            Self::Us2500 => 2500,
+        }
+    }
+}
+
 #[derive(Format, PartialEq, Debug, Copy, Clone)]
 pub enum AgcDelaySettling {
     Us10,
@@ -113,7 +161,15 @@ impl Register for Delay2 {
     }
 }
 
-impl WritableRegister for Delay2 {}
+impl WritableRegister for Delay2 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.delay2)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.delay2)
+    }
+}
 
 impl From<Delay2> for u8 {
     fn from(val: Delay2) -> u8 {
@@ -21,7 +21,15 @@ impl Register for Fifo1 {
     }
 }
 
-impl WritableRegister for Fifo1 {}
+impl WritableRegister for Fifo1 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.fifo1)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.fifo1)
+    }
+}
 
 impl From<Fifo1> for u8 {
     fn from(val: Fifo1) -> u8 {
@@ -52,7 +60,15 @@ impl Register for Fifo2 {
     }
 }
 
-impl WritableRegister for Fifo2 {}
+impl WritableRegister for Fifo2 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.fifo2)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.fifo2)
+    }
+}
 
 impl From<Fifo2> for u8 {
     fn from(val: Fifo2) -> u8 {
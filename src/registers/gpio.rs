@@ -39,6 +39,13 @@ impl From<GpioPinFunction> for u8 {
     }
 }
 
+/// The general purpose I/O pins of the A7105
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub enum GioPin {
+    Gio1,
+    Gio2,
+}
+
 #[derive(Format, PartialEq, Debug, Copy, Clone)]
 pub struct Gpio1PinControl {
     pub pin_function: GpioPinFunction,
@@ -62,7 +69,15 @@ impl Register for Gpio1PinControl {
     }
 }
 
-impl WritableRegister for Gpio1PinControl {}
+impl WritableRegister for Gpio1PinControl {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.gpio1_pin_control)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.gpio1_pin_control)
+    }
+}
 
 impl From<Gpio1PinControl> for u8 {
     fn from(val: Gpio1PinControl) -> u8 {
@@ -95,7 +110,15 @@ impl Register for Gpio2PinControl {
     }
 }
 
-impl WritableRegister for Gpio2PinControl {}
+impl WritableRegister for Gpio2PinControl {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.gpio2_pin_control)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.gpio2_pin_control)
+    }
+}
 
 impl From<Gpio2PinControl> for u8 {
     fn from(val: Gpio2PinControl) -> u8 {
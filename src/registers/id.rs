@@ -1,11 +1,173 @@
 use super::*;
 use defmt::Format;
+use rand_core::RngCore;
 
 #[derive(Format, PartialEq, Debug, Copy, Clone, Default)]
 pub struct IdData {
     pub id: u32,
 }
 
+impl IdData {
+    /// The longest run of identical bits tolerated in a compliant ID
+    pub const MAX_RUN: u8 = 5;
+    /// The fewest bit transitions tolerated in a compliant ID
+    pub const MIN_TRANSITIONS: u8 = 8;
+    /// The most bit transitions tolerated in a compliant ID
+    pub const MAX_TRANSITIONS: u8 = 24;
+    /// The longest run of alternating bits tolerated in a compliant ID, including the leading
+    /// nibble that continues the preamble
+    pub const MAX_ALTERNATING: u8 = 8;
+    /// The largest aperiodic autocorrelation sidelobe tolerated in a compliant ID
+    pub const MAX_SIDELOBE: u8 = 8;
+
+    /// Returns the ID bits in the order they are sent over the air, MSB first
+    ///
+    /// ID byte 0 is held in the least significant byte of [`IdData::id`] and is sent first.
+    fn air_bits(&self) -> u32 {
+        self.id.swap_bytes()
+    }
+
+    /// Returns the length of the longest run of consecutive bit pairs for which `pair` holds
+    fn longest_run(&self, pair: impl Fn(bool, bool) -> bool) -> u8 {
+        let bits = self.air_bits();
+        let (mut longest, mut run) = (1, 1);
+        for i in (0..31).rev() {
+            let prev = bits & (1 << (i + 1)) != 0;
+            let bit = bits & (1 << i) != 0;
+            run = if pair(prev, bit) { run + 1 } else { 1 };
+            longest = longest.max(run);
+        }
+        longest
+    }
+
+    /// Returns the magnitude of the largest aperiodic autocorrelation sidelobe of the ID
+    fn autocorrelation_peak(&self) -> u8 {
+        let bits = self.air_bits();
+        (1..32u32)
+            .map(|shift| {
+                let overlap = 32 - shift;
+                let mask = (1u32 << overlap) - 1;
+                let disagreements = ((bits >> shift) ^ bits) & mask;
+                (overlap as i32 - 2 * disagreements.count_ones() as i32).unsigned_abs() as u8
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Checks the ID against the datasheet recommendations for a robust sync word, returning
+    /// every rule it violates
+    ///
+    /// The checks assume the full 4 byte ID length is in use. An empty result means the ID is
+    /// compliant.
+    pub fn validate(&self) -> IdViolations {
+        let mut violations = IdViolations::default();
+
+        if !matches!(self.id & 0xF0, 0x50 | 0xA0) {
+            violations.push(IdViolation::FirstByte);
+        }
+
+        let length = self.longest_run(|a, b| a == b);
+        if length > Self::MAX_RUN {
+            violations.push(IdViolation::LongRun { length });
+        }
+
+        let bits = self.air_bits();
+        let count = ((bits ^ (bits >> 1)) & 0x7FFF_FFFF).count_ones() as u8;
+        if !(Self::MIN_TRANSITIONS..=Self::MAX_TRANSITIONS).contains(&count) {
+            violations.push(IdViolation::Transitions { count });
+        }
+
+        let length = self.longest_run(|a, b| a != b);
+        if length > Self::MAX_ALTERNATING {
+            violations.push(IdViolation::PreambleLike { length });
+        }
+
+        let sidelobe = self.autocorrelation_peak();
+        if sidelobe > Self::MAX_SIDELOBE {
+            violations.push(IdViolation::Autocorrelation { sidelobe });
+        }
+
+        violations
+    }
+
+    /// Draws IDs from the provided random number generator until a compliant one is found
+    ///
+    /// The high nibble of ID byte 0 is always forced to `0x5` or `0xA`, so that draws are
+    /// never rejected for [`IdViolation::FirstByte`].
+    pub fn generate<R: RngCore>(rng: &mut R) -> Self {
+        loop {
+            let id = rng.next_u32();
+            let nibble = if id & 0x80 != 0 { 0xA0 } else { 0x50 };
+            let candidate = Self {
+                id: (id & !0xF0) | nibble,
+            };
+            if candidate.validate().is_empty() {
+                return candidate;
+            }
+        }
+    }
+}
+
+/// A rule of good sync word design violated by an [`IdData`]
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub enum IdViolation {
+    /// The high nibble of ID byte 0 is neither `0x5` nor `0xA`
+    FirstByte,
+    /// The ID contains a run of identical bits longer than [`IdData::MAX_RUN`]
+    LongRun {
+        /// The length of the longest run of identical bits
+        length: u8,
+    },
+    /// The number of bit transitions is outside of [`IdData::MIN_TRANSITIONS`] to
+    /// [`IdData::MAX_TRANSITIONS`]
+    Transitions {
+        /// The number of transitions between adjacent bits
+        count: u8,
+    },
+    /// The ID contains a run of alternating bits longer than [`IdData::MAX_ALTERNATING`],
+    /// making it hard to tell apart from the preamble
+    PreambleLike {
+        /// The length of the longest run of alternating bits
+        length: u8,
+    },
+    /// The largest autocorrelation sidelobe exceeds [`IdData::MAX_SIDELOBE`]
+    Autocorrelation {
+        /// The magnitude of the largest aperiodic autocorrelation sidelobe, being the
+        /// difference between the agreeing and disagreeing bits of the ID and a shifted copy
+        /// of itself
+        sidelobe: u8,
+    },
+}
+
+/// The set of violations returned by [`IdData::validate`]
+#[derive(Format, PartialEq, Debug, Copy, Clone, Default)]
+pub struct IdViolations {
+    violations: [Option<IdViolation>; 5],
+    len: usize,
+}
+
+impl IdViolations {
+    fn push(&mut self, violation: IdViolation) {
+        self.violations[self.len] = Some(violation);
+        self.len += 1;
+    }
+
+    /// Returns `true` if no rules were violated
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of rules violated
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns an iterator over the violated rules
+    pub fn iter(&self) -> impl Iterator<Item = &IdViolation> {
+        self.violations[..self.len].iter().flatten()
+    }
+}
+
 impl Register for IdData {
     fn id() -> u8 {
         0x06
@@ -13,7 +175,19 @@ impl Register for IdData {
 }
 
 impl ReadableRegister<4> for IdData {}
-impl WritableRegister<4> for IdData {}
+impl WritableRegister<4> for IdData {
+    fn readback_mask() -> Option<[u8; 4]> {
+        Some([0xFF; 4])
+    }
+
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.id_data)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.id_data)
+    }
+}
 
 impl From<u32> for IdData {
     fn from(id: u32) -> Self {
@@ -32,8 +206,64 @@ mod test {
     use super::super::Register as _;
     use super::*;
 
+    struct Counter(u32);
+
+    impl RngCore for Counter {
+        fn next_u32(&mut self) -> u32 {
+            self.0 = self.0.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            self.0
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            rand_core::impls::next_u64_via_u32(self)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            rand_core::impls::fill_bytes_via_next(self, dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
     #[test]
     fn test_id() {
         assert_eq!(IdData::id(), 0x6);
     }
+
+    #[test]
+    fn test_validate_constant() {
+        let violations = IdData { id: 0x0000_0000 }.validate();
+        let expected = [
+            IdViolation::FirstByte,
+            IdViolation::LongRun { length: 32 },
+            IdViolation::Transitions { count: 0 },
+            IdViolation::Autocorrelation { sidelobe: 31 },
+        ];
+        assert_eq!(violations.len(), expected.len());
+        assert!(violations.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn test_validate_preamble() {
+        let violations = IdData { id: 0x5555_5555 }.validate();
+        let expected = [
+            IdViolation::Transitions { count: 31 },
+            IdViolation::PreambleLike { length: 32 },
+            IdViolation::Autocorrelation { sidelobe: 31 },
+        ];
+        assert!(violations.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn test_generate() {
+        let mut rng = Counter(42);
+        for _ in 0..100 {
+            let id = IdData::generate(&mut rng);
+            assert!(matches!(id.id & 0xF0, 0x50 | 0xA0));
+            assert!(id.validate().is_empty());
+        }
+    }
 }
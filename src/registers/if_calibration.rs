@@ -13,7 +13,15 @@ impl Register for IfCalibration1Config {
     }
 }
 
-impl WritableRegister for IfCalibration1Config {}
+impl WritableRegister for IfCalibration1Config {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.if_calibration1_config)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.if_calibration1_config)
+    }
+}
 
 impl From<IfCalibration1Config> for u8 {
     fn from(cfg: IfCalibration1Config) -> u8 {
@@ -16,6 +16,7 @@ pub use rc_osc::*;
 pub use rssi::*;
 pub use rx::*;
 pub use tx::*;
+pub use tx_test::*;
 pub use vco::*;
 
 mod adc;
@@ -36,8 +37,11 @@ mod rc_osc;
 mod rssi;
 mod rx;
 mod tx;
+mod tx_test;
 mod vco;
 
+use crate::shadow::ShadowRegisters;
+
 /// The generic top level trait for all register values
 pub trait Register {
     fn id() -> u8;
@@ -46,8 +50,34 @@ pub trait Register {
 /// A marker trait for registers that are readable
 pub trait ReadableRegister<const N: usize = 1>: Register + FromSlice<N> {}
 
-/// A marker trait for registers that are writable
-pub trait WritableRegister<const N: usize = 1>: Register + IntoSlice<N> {}
+/// A trait for registers that are writable
+pub trait WritableRegister<const N: usize = 1>: Register + IntoSlice<N> {
+    /// Returns the bits that read back as they were written, or `None` if the register
+    /// can not be read back
+    ///
+    /// Read only status bits and bits that clear automatically are excluded from the mask.
+    fn readback_mask() -> Option<[u8; N]> {
+        None
+    }
+
+    /// Returns the slot this register is kept in by a [`ShadowRegisters`] cache, or `None`
+    /// if writes to the register are not cached
+    fn shadow_slot(_shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// Returns the slot this register is kept in by a [`ShadowRegisters`] cache for reading,
+    /// which must match [`WritableRegister::shadow_slot`]
+    fn shadow_ref(_shadow: &ShadowRegisters) -> Option<&Option<Self>>
+    where
+        Self: Sized,
+    {
+        None
+    }
+}
 
 /// A utility trait for representing types that can be created from a slice of bytes of a specific length
 ///
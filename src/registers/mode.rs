@@ -116,7 +116,20 @@ impl Register for ModeControl {
 
 impl ReadableRegister for ModeControl {}
 
-impl WritableRegister for ModeControl {}
+impl WritableRegister for ModeControl {
+    fn readback_mask() -> Option<[u8; 1]> {
+        // Bit 4 reads back the carrier detect status, while ADCM clears once done
+        Some([0b1110_1110])
+    }
+
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.mode_control)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.mode_control)
+    }
+}
 
 impl From<u8> for ModeControl {
     fn from(val: u8) -> Self {
@@ -152,7 +165,7 @@ impl From<ModeControl> for u8 {
 
 #[cfg(test)]
 mod test {
-    use super::super::Register as _;
+    use super::super::{Register as _, WritableRegister as _};
     use super::*;
 
     #[test]
@@ -167,4 +180,17 @@ mod test {
 
         assert_eq!(ModeControl::id(), 0x1);
     }
+
+    #[test]
+    fn test_mode_control_readback_mask() {
+        // The ADC measurement enable bit clears itself and must not be verified
+        let mask = ModeControl::readback_mask().unwrap()[0];
+        let adc: u8 = ModeControl {
+            adc_measurement_enabled: true,
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(adc & mask, 0);
+        assert_eq!(Reset::readback_mask(), None);
+    }
 }
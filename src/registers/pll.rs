@@ -13,7 +13,19 @@ impl Register for Pll1 {
 }
 
 impl ReadableRegister for Pll1 {}
-impl WritableRegister for Pll1 {}
+impl WritableRegister for Pll1 {
+    fn readback_mask() -> Option<[u8; 1]> {
+        Some([0xFF])
+    }
+
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.pll1)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.pll1)
+    }
+}
 
 impl From<u8> for Pll1 {
     fn from(channel: u8) -> Self {
@@ -47,6 +59,18 @@ impl Default for Pll2 {
     }
 }
 
+impl Pll2 {
+    /// Returns the crystal reference frequency, F_XREF, after the optional doubler
+    pub fn reference_hz(&self, xtal_hz: u32) -> u32 {
+        xtal_hz * (u32::from(self.crystal_freq_doubler) + 1)
+    }
+
+    /// Returns the PLL comparison frequency, F_PFD
+    pub fn pfd_hz(&self, xtal_hz: u32) -> u32 {
+        self.reference_hz(xtal_hz) / (u32::from(self.rf_pll_ref_counter.min(0b11)) + 1)
+    }
+}
+
 impl Register for Pll2 {
     fn id() -> u8 {
         0x10
@@ -54,7 +78,19 @@ impl Register for Pll2 {
 }
 
 impl ReadableRegister for Pll2 {}
-impl WritableRegister for Pll2 {}
+impl WritableRegister for Pll2 {
+    fn readback_mask() -> Option<[u8; 1]> {
+        Some([0xFF])
+    }
+
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.pll2)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.pll2)
+    }
+}
 
 impl From<u8> for Pll2 {
     fn from(val: u8) -> Self {
@@ -94,7 +130,19 @@ impl Register for Pll3 {
 }
 
 impl ReadableRegister for Pll3 {}
-impl WritableRegister for Pll3 {}
+impl WritableRegister for Pll3 {
+    fn readback_mask() -> Option<[u8; 1]> {
+        Some([0xFF])
+    }
+
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.pll3)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.pll3)
+    }
+}
 
 impl From<u8> for Pll3 {
     fn from(bip: u8) -> Self {
@@ -120,7 +168,20 @@ impl Register for Pll4 {
 }
 
 impl ReadableRegister for Pll4 {}
-impl WritableRegister for Pll4 {}
+impl WritableRegister for Pll4 {
+    fn readback_mask() -> Option<[u8; 1]> {
+        // Reads return the auto calibration result rather than the value written
+        None
+    }
+
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.pll4)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.pll4)
+    }
+}
 
 impl From<u8> for Pll4 {
     fn from(bfp: u8) -> Self {
@@ -153,7 +214,20 @@ impl Register for Pll5 {
 }
 
 impl ReadableRegister for Pll5 {}
-impl WritableRegister for Pll5 {}
+impl WritableRegister for Pll5 {
+    fn readback_mask() -> Option<[u8; 1]> {
+        // Reads return the auto calibration result rather than the value written
+        None
+    }
+
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.pll5)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.pll5)
+    }
+}
 
 impl From<u8> for Pll5 {
     fn from(bfp: u8) -> Self {
@@ -169,7 +243,7 @@ impl From<Pll5> for u8 {
 
 #[cfg(test)]
 mod test {
-    use super::super::Register as _;
+    use super::super::{Register as _, WritableRegister as _};
     use super::*;
 
     #[test]
@@ -211,4 +285,12 @@ mod test {
 
         assert_eq!(Pll5::id(), 0x13);
     }
+
+    #[test]
+    fn test_pll_readback_mask() {
+        assert_eq!(Pll1::readback_mask(), Some([0xFF]));
+        // PLL IV and V read back auto calibration results, so are never verified
+        assert_eq!(Pll4::readback_mask(), None);
+        assert_eq!(Pll5::readback_mask(), None);
+    }
 }
@@ -23,7 +23,15 @@ impl Register for RcOsc3 {
     }
 }
 
-impl WritableRegister for RcOsc3 {}
+impl WritableRegister for RcOsc3 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.rc_osc3)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.rc_osc3)
+    }
+}
 
 impl From<RcOsc3> for u8 {
     fn from(val: RcOsc3) -> u8 {
@@ -20,7 +20,15 @@ impl Register for RssiCarrierDetectThreshold {
     }
 }
 
-impl WritableRegister for RssiCarrierDetectThreshold {}
+impl WritableRegister for RssiCarrierDetectThreshold {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.rssi_carrier_detect_threshold)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.rssi_carrier_detect_threshold)
+    }
+}
 
 impl From<RssiCarrierDetectThreshold> for u8 {
     fn from(val: RssiCarrierDetectThreshold) -> u8 {
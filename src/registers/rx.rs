@@ -32,7 +32,15 @@ impl Register for Rx {
     }
 }
 
-impl WritableRegister for Rx {}
+impl WritableRegister for Rx {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.rx)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.rx)
+    }
+}
 
 impl From<Rx> for u8 {
     fn from(val: Rx) -> u8 {
@@ -88,7 +96,19 @@ impl Register for RxGain1 {
     }
 }
 
-impl WritableRegister for RxGain1 {}
+impl WritableRegister for RxGain1 {
+    fn readback_mask() -> Option<[u8; 1]> {
+        Some([0b1011_1111])
+    }
+
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.rx_gain1)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.rx_gain1)
+    }
+}
 impl ReadableRegister for RxGain1 {}
 
 impl From<RxGain1> for u8 {
@@ -131,6 +151,102 @@ impl From<u8> for RxGain1 {
     }
 }
 
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct RxGain2 {
+    /// Reserved for internal usage
+    pub rh: u8,
+}
+
+impl Default for RxGain2 {
+    fn default() -> Self {
+        Self { rh: 0b0000_1010 }
+    }
+}
+
+impl Register for RxGain2 {
+    fn id() -> u8 {
+        0x1A
+    }
+}
+
+impl WritableRegister for RxGain2 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.rx_gain2)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.rx_gain2)
+    }
+}
+
+impl From<RxGain2> for u8 {
+    fn from(val: RxGain2) -> u8 {
+        val.rh
+    }
+}
+
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct RxGain3 {
+    /// Reserved for internal usage
+    pub rl: u8,
+}
+
+impl Default for RxGain3 {
+    fn default() -> Self {
+        Self { rl: 0b1011_0100 }
+    }
+}
+
+impl Register for RxGain3 {
+    fn id() -> u8 {
+        0x1B
+    }
+}
+
+impl WritableRegister for RxGain3 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.rx_gain3)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.rx_gain3)
+    }
+}
+
+impl From<RxGain3> for u8 {
+    fn from(val: RxGain3) -> u8 {
+        val.rl
+    }
+}
+
+#[derive(Format, PartialEq, Debug, Copy, Clone, Default)]
+pub struct RxGain4 {
+    pub vga_calibration_enable: bool,
+    // There are other fields in here, but they are for internal use only so we don't expose them
+}
+
+impl Register for RxGain4 {
+    fn id() -> u8 {
+        0x1C
+    }
+}
+
+impl WritableRegister for RxGain4 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.rx_gain4)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.rx_gain4)
+    }
+}
+
+impl From<RxGain4> for u8 {
+    fn from(val: RxGain4) -> u8 {
+        0b0000_1010 | u8::from(val.vga_calibration_enable)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::super::Register as _;
@@ -151,4 +267,28 @@ mod test {
 
         assert_eq!(RxGain1::id(), 0x19);
     }
+
+    #[test]
+    fn test_rx_gain2_register() {
+        let default: u8 = RxGain2::default().into();
+        assert_eq!(default, 0b0000_1010);
+
+        assert_eq!(RxGain2::id(), 0x1A);
+    }
+
+    #[test]
+    fn test_rx_gain3_register() {
+        let default: u8 = RxGain3::default().into();
+        assert_eq!(default, 0b1011_0100);
+
+        assert_eq!(RxGain3::id(), 0x1B);
+    }
+
+    #[test]
+    fn test_rx_gain4_register() {
+        let default: u8 = RxGain4::default().into();
+        assert_eq!(default, 0b0000_1010);
+
+        assert_eq!(RxGain4::id(), 0x1C);
+    }
 }
@@ -36,7 +36,15 @@ impl Register for Tx1 {
     }
 }
 
-impl WritableRegister for Tx1 {}
+impl WritableRegister for Tx1 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.tx1)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.tx1)
+    }
+}
 
 impl From<Tx1> for u8 {
     fn from(val: Tx1) -> u8 {
@@ -65,13 +73,45 @@ impl Default for Tx2 {
     }
 }
 
+impl Tx2 {
+    /// Computes the deviation setting closest to `deviation_hz` for the given PLL comparison
+    /// frequency and [`Tx1::fdp`], returning `None` if it is out of range
+    ///
+    /// The deviation is F_PFD * 127 * (FD + 1) * 2^FDP / 2^24, as given in the datasheet.
+    pub fn from_deviation(pfd_hz: u32, fdp: u8, deviation_hz: u32) -> Option<Self> {
+        let step = (u64::from(pfd_hz) * 127) << fdp.min(0b111);
+        let steps = ((u64::from(deviation_hz) << 24) + step / 2) / step;
+        match steps {
+            1..=32 => Some(Self {
+                fd: steps as u8 - 1,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the frequency deviation in Hz for the given PLL comparison frequency and
+    /// [`Tx1::fdp`]
+    pub fn deviation_hz(&self, pfd_hz: u32, fdp: u8) -> u32 {
+        let steps = u64::from(self.fd.min(0b11111)) + 1;
+        (((u64::from(pfd_hz) * 127 * steps) << fdp.min(0b111)) >> 24) as u32
+    }
+}
+
 impl Register for Tx2 {
     fn id() -> u8 {
         0x15
     }
 }
 
-impl WritableRegister for Tx2 {}
+impl WritableRegister for Tx2 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.tx2)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.tx2)
+    }
+}
 
 impl From<Tx2> for u8 {
     fn from(val: Tx2) -> u8 {
@@ -99,4 +139,27 @@ mod test {
 
         assert_eq!(Tx2::id(), 0x15);
     }
+
+    #[test]
+    fn test_tx2_deviation() {
+        // The recommended settings listed in the datasheet
+        for (pfd_hz, deviation_hz, fd) in [
+            (16_000_000, 124_000, 0x0F),
+            (24_000_000, 127_000, 0x0A),
+            (32_000_000, 124_000, 0x07),
+            (16_000_000, 186_000, 0x17),
+            (24_000_000, 186_000, 0x0F),
+            (32_000_000, 186_000, 0x0B),
+        ] {
+            assert_eq!(
+                Tx2::from_deviation(pfd_hz, 0b110, deviation_hz),
+                Some(Tx2 { fd })
+            );
+        }
+
+        // Listed as 186kHz in the datasheet
+        assert_eq!(Tx2 { fd: 0x17 }.deviation_hz(16_000_000, 0b110), 186_035);
+        assert_eq!(Tx2::from_deviation(16_000_000, 0b110, 1_000_000), None);
+        assert_eq!(Tx2::from_deviation(16_000_000, 0b110, 1_000), None);
+    }
 }
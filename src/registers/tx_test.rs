@@ -0,0 +1,173 @@
+use super::*;
+use defmt::Format;
+
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct TxTest {
+    /// TX current setting, recommended to be left disabled
+    pub tx_current: bool,
+    /// PA current setting, from 0 to 3
+    pub pa_current: u8,
+    /// TX buffer setting, from 0 to 7
+    pub buffer: u8,
+}
+
+impl Default for TxTest {
+    fn default() -> Self {
+        Self {
+            tx_current: false,
+            pa_current: 0b10,
+            buffer: 0b111,
+        }
+    }
+}
+
+impl Register for TxTest {
+    fn id() -> u8 {
+        0x28
+    }
+}
+
+impl WritableRegister for TxTest {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.tx_test)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.tx_test)
+    }
+}
+
+impl From<TxTest> for u8 {
+    fn from(val: TxTest) -> u8 {
+        u8::from(val.tx_current) << 5 | val.pa_current.min(0b11) << 3 | val.buffer.min(0b111)
+    }
+}
+
+/// A transmit power level from the typical output power table in the datasheet
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct TxPower {
+    /// PA current setting, from 0 to 3
+    pub pa_current: u8,
+    /// TX buffer setting, from 0 to 7
+    pub buffer: u8,
+    /// Typical output power, in tenths of a dBm
+    pub deci_dbm: i16,
+    /// Typical supply current while transmitting, in µA
+    pub current_ua: u32,
+}
+
+impl TxPower {
+    /// Every level listed in the datasheet, ordered by PA current then TX buffer setting
+    pub const TABLE: [TxPower; 32] = {
+        const DBM: [[i16; 8]; 4] = [
+            [-233, -192, -166, -132, -109, -89, -48, -20],
+            [-207, -169, -138, -104, -83, -63, -34, -5],
+            [-187, -152, -120, -85, -68, -48, -15, 1],
+            [-176, -145, -100, -72, -51, -35, -5, 13],
+        ];
+        const CURRENT_UA: [[u32; 8]; 4] = [
+            [
+                12_400, 12_500, 12_600, 12_900, 13_300, 13_600, 14_900, 16_900,
+            ],
+            [
+                13_400, 13_500, 13_700, 13_900, 14_300, 14_500, 15_900, 18_000,
+            ],
+            [
+                15_300, 15_400, 15_500, 15_800, 16_100, 16_500, 17_600, 19_000,
+            ],
+            [
+                17_600, 17_700, 17_780, 18_100, 18_200, 18_500, 19_500, 21_250,
+            ],
+        ];
+
+        let mut table = [TxPower {
+            pa_current: 0,
+            buffer: 0,
+            deci_dbm: 0,
+            current_ua: 0,
+        }; 32];
+        let mut i = 0;
+        while i < table.len() {
+            let (pa, buffer) = (i / 8, i % 8);
+            table[i] = TxPower {
+                pa_current: pa as u8,
+                buffer: buffer as u8,
+                deci_dbm: DBM[pa][buffer],
+                current_ua: CURRENT_UA[pa][buffer],
+            };
+            i += 1;
+        }
+        table
+    };
+
+    /// The level selected by the reset value of [`TxTest`]
+    pub const RESET: TxPower = Self::TABLE[0b10 * 8 + 0b111];
+
+    /// Returns the level whose typical output power is closest to `dbm`, preferring the
+    /// level drawing less current when two are equally close
+    pub fn nearest(dbm: i8) -> TxPower {
+        let target = i16::from(dbm) * 10;
+        Self::TABLE
+            .into_iter()
+            .min_by_key(|level| ((level.deci_dbm - target).unsigned_abs(), level.current_ua))
+            .unwrap_or_default()
+    }
+
+    /// Returns the level matching the given register value, if it is listed in the datasheet
+    pub fn from_tx_test(tx_test: &TxTest) -> Option<TxPower> {
+        if tx_test.tx_current || tx_test.pa_current > 0b11 || tx_test.buffer > 0b111 {
+            return None;
+        }
+        Self::TABLE
+            .get(usize::from(tx_test.pa_current) * 8 + usize::from(tx_test.buffer))
+            .copied()
+    }
+
+    /// Returns the register value that selects this level
+    pub fn tx_test(&self) -> TxTest {
+        TxTest {
+            tx_current: false,
+            pa_current: self.pa_current,
+            buffer: self.buffer,
+        }
+    }
+}
+
+impl Default for TxPower {
+    fn default() -> Self {
+        Self::RESET
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::Register as _;
+    use super::*;
+
+    #[test]
+    fn test_tx_test() {
+        let default: u8 = TxTest::default().into();
+        assert_eq!(default, 0b0001_0111);
+
+        assert_eq!(TxTest::id(), 0x28);
+    }
+
+    #[test]
+    fn test_tx_power() {
+        assert_eq!(
+            TxPower::from_tx_test(&TxTest::default()),
+            Some(TxPower::default())
+        );
+        assert_eq!(TxPower::default().deci_dbm, 1);
+
+        // The datasheet recommends PAC = 2 and TBG = 7 for 0dBm
+        assert_eq!(TxPower::nearest(0).tx_test(), TxTest::default());
+        assert_eq!(TxPower::nearest(5).deci_dbm, 13);
+        assert_eq!(TxPower::nearest(-10).deci_dbm, -100);
+        assert_eq!(TxPower::nearest(-30).deci_dbm, -233);
+
+        // -1.5dBm and -0.5dBm are equally close to -1dBm, so the cheapest of those wins
+        let level = TxPower::nearest(-1);
+        assert_eq!((level.pa_current, level.buffer), (2, 6));
+    }
+}
@@ -19,7 +19,15 @@ impl Register for VcoCurrentCalibration {
     }
 }
 
-impl WritableRegister for VcoCurrentCalibration {}
+impl WritableRegister for VcoCurrentCalibration {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.vco_current_calibration)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.vco_current_calibration)
+    }
+}
 
 impl From<VcoCurrentCalibration> for u8 {
     fn from(val: VcoCurrentCalibration) -> u8 {
@@ -66,7 +74,15 @@ impl Register for VcoSingleBandCalibration1 {
     }
 }
 
-impl WritableRegister for VcoSingleBandCalibration1 {}
+impl WritableRegister for VcoSingleBandCalibration1 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.vco_single_band_calibration1)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.vco_single_band_calibration1)
+    }
+}
 
 impl From<VcoSingleBandCalibration1> for u8 {
     fn from(val: VcoSingleBandCalibration1) -> u8 {
@@ -172,7 +188,15 @@ impl Register for VcoSingleBandCalibration2 {
     }
 }
 
-impl WritableRegister for VcoSingleBandCalibration2 {}
+impl WritableRegister for VcoSingleBandCalibration2 {
+    fn shadow_slot(shadow: &mut ShadowRegisters) -> Option<&mut Option<Self>> {
+        Some(&mut shadow.vco_single_band_calibration2)
+    }
+
+    fn shadow_ref(shadow: &ShadowRegisters) -> Option<&Option<Self>> {
+        Some(&shadow.vco_single_band_calibration2)
+    }
+}
 
 impl From<VcoSingleBandCalibration2> for u8 {
     fn from(val: VcoSingleBandCalibration2) -> u8 {
@@ -0,0 +1,409 @@
+//! A stop-and-wait ARQ link layer built on top of [`A7105::transmit`] and [`A7105::receive`]
+//!
+//! Every data frame carries a sequence number and must be acknowledged by the peer. After
+//! transmitting, the sender automatically switches to [`Mode::Rx`](crate::commands::Mode::Rx)
+//! and waits for the ACK within a configurable window, retransmitting with an exponential
+//! backoff if none arrives. The receiver acknowledges every data frame it sees, but only
+//! delivers a frame to the application once, suppressing retransmitted duplicates.
+//!
+//! Frames are always a fixed `N` bytes long, matching the FIFO length configured through
+//! [`Fifo1`](crate::registers::Fifo1), and consist of a 3 byte header followed by the payload.
+//!
+//! ```ignore
+//! use a7105::prelude::*;
+//! use a7105::reliable::{ReliableConfig, ReliableLink};
+//!
+//! # let (a7105_spi_peripheral, mut delay) = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//!
+//! // Both radios must be configured for 16 byte packets
+//! let mut link: ReliableLink<16> = ReliableLink::new(ReliableConfig::default());
+//! link.send(&mut radio, &mut delay, b"hello").await.unwrap();
+//!
+//! let mut buf = [0; 16];
+//! let len = link.recv(&mut radio, &mut delay, &mut buf, 100_000).await.unwrap();
+//! ```
+
+use crate::{LinkError, ReadPacketError, A7105};
+use defmt::Format;
+
+#[cfg(feature = "blocking")]
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs, spi::SpiDevice};
+
+const HEADER_LEN: usize = 3;
+const KIND_DATA: u8 = 0x01;
+const KIND_ACK: u8 = 0x02;
+
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+enum FrameKind {
+    Data,
+    Ack,
+}
+
+/// Writes a frame of the given kind into `frame`, returning `None` if the payload does not fit
+fn encode(kind: FrameKind, seq: u8, payload: &[u8], frame: &mut [u8]) -> Option<()> {
+    let end = HEADER_LEN + payload.len();
+    if end > frame.len() || payload.len() > u8::MAX as usize {
+        return None;
+    }
+
+    frame[0] = match kind {
+        FrameKind::Data => KIND_DATA,
+        FrameKind::Ack => KIND_ACK,
+    };
+    frame[1] = seq;
+    frame[2] = payload.len() as u8;
+    frame[HEADER_LEN..end].copy_from_slice(payload);
+    frame[end..].fill(0);
+    Some(())
+}
+
+/// Parses a received frame, returning `None` if it is malformed
+fn decode(frame: &[u8]) -> Option<(FrameKind, u8, &[u8])> {
+    if frame.len() < HEADER_LEN {
+        return None;
+    }
+
+    let kind = match frame[0] {
+        KIND_DATA => FrameKind::Data,
+        KIND_ACK => FrameKind::Ack,
+        _ => return None,
+    };
+    let end = HEADER_LEN + frame[2] as usize;
+    let payload = frame.get(HEADER_LEN..end)?;
+    Some((kind, frame[1], payload))
+}
+
+/// Configuration for a [`ReliableLink`]
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct ReliableConfig {
+    /// How long to wait for an ACK after each transmission, in microseconds
+    pub ack_timeout_us: u32,
+    /// The number of times a frame is retransmitted before giving up
+    pub max_retries: u8,
+    /// The delay before the first retransmission, doubled after every subsequent attempt
+    pub backoff_us: u32,
+    /// The upper bound on the delay between retransmissions
+    pub max_backoff_us: u32,
+}
+
+impl Default for ReliableConfig {
+    fn default() -> Self {
+        Self {
+            ack_timeout_us: 5_000,
+            max_retries: 5,
+            backoff_us: 1_000,
+            max_backoff_us: 16_000,
+        }
+    }
+}
+
+/// Delivery statistics gathered by a [`ReliableLink`]
+#[derive(Format, PartialEq, Debug, Copy, Clone, Default)]
+pub struct LinkStats {
+    /// Data frames transmitted, including retransmissions
+    pub frames_sent: u32,
+    /// Data frames that had to be retransmitted
+    pub retransmissions: u32,
+    /// Data frames that were acknowledged by the peer
+    pub delivered: u32,
+    /// Data frames that were never acknowledged by the peer
+    pub failed: u32,
+    /// Data frames received from the peer and delivered to the application
+    pub received: u32,
+    /// Data frames received from the peer that had already been delivered
+    pub duplicates: u32,
+    /// ACKs transmitted to the peer
+    pub acks_sent: u32,
+}
+
+/// A stop-and-wait ARQ link with fixed `N` byte frames
+///
+/// Refer to the [module level documentation](self) for an overview of the protocol.
+pub struct ReliableLink<const N: usize> {
+    config: ReliableConfig,
+    next_seq: u8,
+    last_received: Option<u8>,
+    stats: LinkStats,
+}
+
+impl<const N: usize> ReliableLink<N> {
+    /// The largest payload that fits into a single frame
+    pub const MAX_PAYLOAD: usize = N - HEADER_LEN;
+
+    /// Constructs a new [`ReliableLink`] with the provided configuration
+    pub const fn new(config: ReliableConfig) -> Self {
+        Self {
+            config,
+            next_seq: 0,
+            last_received: None,
+            stats: LinkStats {
+                frames_sent: 0,
+                retransmissions: 0,
+                delivered: 0,
+                failed: 0,
+                received: 0,
+                duplicates: 0,
+                acks_sent: 0,
+            },
+        }
+    }
+
+    /// Returns the delivery statistics gathered so far
+    pub fn stats(&self) -> &LinkStats {
+        &self.stats
+    }
+
+    /// Clears the delivery statistics gathered so far
+    pub fn reset_stats(&mut self) {
+        self.stats = LinkStats::default();
+    }
+
+    /// Sends the payload to the peer, retransmitting until it is acknowledged or all
+    /// retries have been exhausted
+    #[maybe_async::maybe_async]
+    pub async fn send<SPI: SpiDevice, D: DelayNs>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        payload: &[u8],
+    ) -> Result<(), LinkError<SPI::Error>> {
+        let seq = self.next_seq;
+        let mut frame = [0; N];
+        encode(FrameKind::Data, seq, payload, &mut frame).ok_or(LinkError::PayloadTooLarge)?;
+
+        // The sequence number is consumed even if delivery fails, as the peer may have
+        // received the frame and only the ACKs were lost
+        self.next_seq = seq.wrapping_add(1);
+
+        let mut backoff_us = self.config.backoff_us;
+        for attempt in 0..=self.config.max_retries {
+            if attempt > 0 {
+                self.stats.retransmissions += 1;
+                delay.delay_us(backoff_us).await;
+                backoff_us = backoff_us.saturating_mul(2).min(self.config.max_backoff_us);
+            }
+
+            radio.transmit(&frame, delay).await?;
+            self.stats.frames_sent += 1;
+
+            let mut ack = [0; N];
+            match radio
+                .receive(&mut ack, delay, self.config.ack_timeout_us)
+                .await
+            {
+                Ok(()) => {}
+                Err(ReadPacketError::SpiError(e)) => return Err(e.into()),
+                Err(ReadPacketError::PacketError(_) | ReadPacketError::Timeout) => continue,
+            }
+
+            if let Some((FrameKind::Ack, ack_seq, _)) = decode(&ack) {
+                if ack_seq == seq {
+                    self.stats.delivered += 1;
+                    return Ok(());
+                }
+            }
+        }
+
+        self.stats.failed += 1;
+        Err(LinkError::NoAck)
+    }
+
+    /// Waits up to `timeout_us` microseconds for a new data frame from the peer, writing its
+    /// payload into the provided buffer and returning the payload length
+    ///
+    /// Every data frame received is acknowledged, but retransmissions of a frame that was
+    /// already delivered are silently dropped. Corrupted frames are ignored and do not end
+    /// the wait, while the timeout covers the whole wait rather than restarting after each
+    /// ignored frame. A frame whose payload does not fit into the buffer is not
+    /// acknowledged, so that the peer does not consider it delivered.
+    #[maybe_async::maybe_async]
+    pub async fn recv<SPI: SpiDevice, D: DelayNs>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        buf: &mut [u8],
+        timeout_us: u32,
+    ) -> Result<usize, LinkError<SPI::Error>> {
+        let mut remaining_us = timeout_us;
+        loop {
+            let mut frame = [0; N];
+            match radio
+                .receive_within(&mut frame, delay, &mut remaining_us)
+                .await
+            {
+                Ok(()) => {}
+                Err(ReadPacketError::SpiError(e)) => return Err(e.into()),
+                Err(ReadPacketError::PacketError(_)) => continue,
+                Err(ReadPacketError::Timeout) => return Err(LinkError::Timeout),
+            }
+
+            let Some((FrameKind::Data, seq, payload)) = decode(&frame) else {
+                continue;
+            };
+            let duplicate = self.last_received == Some(seq);
+            if !duplicate && payload.len() > buf.len() {
+                return Err(LinkError::BufferTooSmall);
+            }
+
+            let mut ack = [0; N];
+            encode(FrameKind::Ack, seq, &[], &mut ack).ok_or(LinkError::PayloadTooLarge)?;
+            radio.transmit(&ack, delay).await?;
+            self.stats.acks_sent += 1;
+
+            if duplicate {
+                self.stats.duplicates += 1;
+                continue;
+            }
+
+            buf[..payload.len()].copy_from_slice(payload);
+            self.last_received = Some(seq);
+            self.stats.received += 1;
+            return Ok(payload.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::{run, MockDelay, MockSpi};
+    use std::{boxed::Box, vec::Vec};
+
+    extern crate std;
+
+    fn data(seq: u8, payload: &[u8]) -> Vec<u8> {
+        let mut frame = [0; 8];
+        encode(FrameKind::Data, seq, payload, &mut frame).unwrap();
+        frame.to_vec()
+    }
+
+    fn ack(seq: u8) -> Vec<u8> {
+        let mut frame = [0; 8];
+        encode(FrameKind::Ack, seq, &[], &mut frame).unwrap();
+        frame.to_vec()
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let mut frame = [0xFF; 8];
+        encode(FrameKind::Data, 7, &[1, 2, 3], &mut frame).unwrap();
+        assert_eq!(frame, [KIND_DATA, 7, 3, 1, 2, 3, 0, 0]);
+        assert_eq!(decode(&frame), Some((FrameKind::Data, 7, &[1, 2, 3][..])));
+
+        encode(FrameKind::Ack, 9, &[], &mut frame).unwrap();
+        assert_eq!(decode(&frame), Some((FrameKind::Ack, 9, &[][..])));
+    }
+
+    #[test]
+    fn test_frame_limits() {
+        let mut frame = [0; 8];
+        assert!(encode(FrameKind::Data, 0, &[0; 5], &mut frame).is_some());
+        assert!(encode(FrameKind::Data, 0, &[0; 6], &mut frame).is_none());
+        assert_eq!(ReliableLink::<8>::MAX_PAYLOAD, 5);
+
+        // Unknown frame kinds and lengths that overrun the frame are rejected
+        assert_eq!(decode(&[0x7F, 0, 0, 0]), None);
+        assert_eq!(decode(&[KIND_DATA, 0, 2, 0]), None);
+        assert_eq!(decode(&[KIND_DATA, 0]), None);
+    }
+
+    #[test]
+    fn test_send_retransmits_on_lost_ack() {
+        let spi = MockSpi::new();
+        // The first ACK is lost, the second arrives
+        let mut acks = 0;
+        spi.sim().responder = Some(Box::new(move |frame: &[u8]| {
+            acks += 1;
+            (acks > 1).then(|| ack(frame[1]))
+        }));
+        let mut radio = A7105::new(spi.clone());
+        let mut delay = MockDelay::default();
+        let mut link = ReliableLink::<8>::new(ReliableConfig::default());
+
+        run!(link.send(&mut radio, &mut delay, &[1, 2])).unwrap();
+        assert_eq!(spi.sim().sent, [data(0, &[1, 2]), data(0, &[1, 2])]);
+        assert_eq!(link.stats().frames_sent, 2);
+        assert_eq!(link.stats().retransmissions, 1);
+        assert_eq!(link.stats().delivered, 1);
+
+        // An ACK for another frame does not count as delivery
+        spi.sim().responder = Some(Box::new(|_: &[u8]| Some(ack(0))));
+        let result = run!(link.send(&mut radio, &mut delay, &[3]));
+        assert_eq!(result, Err(LinkError::NoAck));
+        assert_eq!(spi.sim().sent.len(), 2 + 6);
+        assert_eq!(link.stats().failed, 1);
+    }
+
+    #[test]
+    fn test_recv_suppresses_duplicates() {
+        let spi = MockSpi::new();
+        spi.sim().push(&data(4, &[1, 2, 3]));
+        // The sender missed the ACK and retransmitted, then sent the next frame
+        spi.sim().push(&data(4, &[1, 2, 3]));
+        spi.sim().push(&data(5, &[4]));
+        let mut radio = A7105::new(spi.clone());
+        let mut delay = MockDelay::default();
+        let mut link = ReliableLink::<8>::new(ReliableConfig::default());
+
+        let mut buf = [0; 5];
+        assert_eq!(
+            run!(link.recv(&mut radio, &mut delay, &mut buf, 1_000)),
+            Ok(3)
+        );
+        assert_eq!(buf[..3], [1, 2, 3]);
+        assert_eq!(
+            run!(link.recv(&mut radio, &mut delay, &mut buf, 1_000)),
+            Ok(1)
+        );
+        assert_eq!(buf[..1], [4]);
+
+        // Every frame is acknowledged, but the duplicate is only delivered once
+        assert_eq!(spi.sim().sent, [ack(4), ack(4), ack(5)]);
+        assert_eq!(link.stats().received, 2);
+        assert_eq!(link.stats().duplicates, 1);
+    }
+
+    #[test]
+    fn test_recv_timeout() {
+        let spi = MockSpi::new();
+        let mut radio = A7105::new(spi.clone());
+        let mut delay = MockDelay::default();
+        let mut link = ReliableLink::<8>::new(ReliableConfig::default());
+
+        let mut buf = [0; 5];
+        let result = run!(link.recv(&mut radio, &mut delay, &mut buf, 1_000));
+        assert_eq!(result, Err(LinkError::Timeout));
+        assert_eq!(delay.elapsed_us, 1_000);
+        assert!(!spi.sim().in_rx());
+
+        // Corrupt frames arriving throughout do not extend the wait
+        spi.sim().airtime_polls = 5;
+        for _ in 0..100 {
+            spi.sim().push_corrupt(&data(0, &[1]));
+        }
+        delay.elapsed_us = 0;
+        let result = run!(link.recv(&mut radio, &mut delay, &mut buf, 1_000));
+        assert_eq!(result, Err(LinkError::Timeout));
+        assert_eq!(delay.elapsed_us, 1_000);
+        assert!(spi.sim().sent.is_empty());
+    }
+
+    #[test]
+    fn test_recv_buffer_too_small() {
+        let spi = MockSpi::new();
+        spi.sim().push(&data(0, &[1, 2, 3]));
+        let mut radio = A7105::new(spi.clone());
+        let mut delay = MockDelay::default();
+        let mut link = ReliableLink::<8>::new(ReliableConfig::default());
+
+        let mut buf = [0; 2];
+        let result = run!(link.recv(&mut radio, &mut delay, &mut buf, 1_000));
+        assert_eq!(result, Err(LinkError::BufferTooSmall));
+        // The frame is not acknowledged, so the sender retransmits it
+        assert!(spi.sim().sent.is_empty());
+        assert_eq!(link.stats().received, 0);
+    }
+}
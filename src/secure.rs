@@ -0,0 +1,489 @@
+//! An authenticated and encrypted link layer built on top of [`A7105::transmit`] and
+//! [`A7105::receive`], enabled through the `secure` feature
+//!
+//! Every payload is sealed with ChaCha20-Poly1305 under a shared 256-bit key, so frames that
+//! were modified in transit or sealed with a different key are rejected. Each frame carries
+//! a 32-bit counter that, together with the id of the sending node and the id of the key,
+//! forms the nonce, and a receiver tracks the counters it has seen from every sender in a
+//! sliding [`ReplayWindow`] so captured frames cannot be played back later.
+//!
+//! Each sender counts its frames independently, so a receiver keeps a separate window for
+//! up to `PEERS` senders per key. A sender is only given a window once one of its frames
+//! has been authenticated, so forged frames can not use up the available windows.
+//!
+//! Frames are always a fixed `N` bytes long, matching the FIFO length configured through
+//! [`Fifo1`](crate::registers::Fifo1), and consist of a 7 byte header, which is
+//! authenticated but not encrypted, followed by the encrypted payload and a 16 byte tag.
+//!
+//! A nonce must never be reused with the same key. The frame counter starts at zero for
+//! every new key, so a node that loses its counter, for example across a reset, must either
+//! restore it through [`SecureLink::set_counter`] or move to a new key through
+//! [`SecureLink::rotate_key`]. Both ends of a link must use different node ids.
+//!
+//! The replay windows are only held in memory, so a receiver that is reset forgets which
+//! frames it has already seen and accepts replays of any frame sealed with its current or
+//! previous key. Where this matters, rotate to a new key after a reset, or persist the
+//! windows and restore them.
+//!
+//! ```ignore
+//! use a7105::prelude::*;
+//! use a7105::secure::SecureLink;
+//!
+//! # let (a7105_spi_peripheral, mut delay, key) = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//!
+//! // Both radios must be configured for 64 byte packets
+//! let mut link: SecureLink<64> = SecureLink::new(0x01, 0, key);
+//! link.send(&mut radio, &mut delay, b"hello").await.unwrap();
+//!
+//! let mut buf = [0; 64];
+//! let len = link.recv(&mut radio, &mut delay, &mut buf, 100_000).await.unwrap();
+//! ```
+
+use crate::{SecureError, A7105};
+use chacha20poly1305::{AeadInPlace, ChaCha20Poly1305, Key, KeyInit, Nonce, Tag};
+use defmt::Format;
+
+#[cfg(feature = "blocking")]
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs, spi::SpiDevice};
+
+const HEADER_LEN: usize = 7;
+const TAG_LEN: usize = 16;
+
+/// Tracks the frame counters received under a key, rejecting any counter seen before
+///
+/// The window covers the highest counter received and the 63 counters before it, so frames
+/// reordered by up to 63 positions are still accepted, while anything older is rejected.
+#[derive(Format, PartialEq, Debug, Copy, Clone, Default)]
+pub struct ReplayWindow {
+    highest: Option<u32>,
+    seen: u64,
+}
+
+impl ReplayWindow {
+    /// The number of counters tracked by the window
+    pub const SIZE: u32 = 64;
+
+    /// Constructs a new, empty [`ReplayWindow`]
+    pub const fn new() -> Self {
+        Self {
+            highest: None,
+            seen: 0,
+        }
+    }
+
+    /// Returns whether a frame with the given counter would be accepted
+    pub fn check(&self, counter: u32) -> bool {
+        match self.highest {
+            None => true,
+            Some(highest) if counter > highest => true,
+            Some(highest) => {
+                let age = highest - counter;
+                age < Self::SIZE && self.seen & (1 << age) == 0
+            }
+        }
+    }
+
+    /// Records the counter as received, returning `false` if it was already rejected by
+    /// [`ReplayWindow::check`]
+    pub fn accept(&mut self, counter: u32) -> bool {
+        if !self.check(counter) {
+            return false;
+        }
+
+        match self.highest {
+            Some(highest) if counter <= highest => self.seen |= 1 << (highest - counter),
+            Some(highest) => {
+                let shift = counter - highest;
+                self.seen = self.seen.checked_shl(shift).unwrap_or(0) | 1;
+                self.highest = Some(counter);
+            }
+            None => {
+                self.seen = 1;
+                self.highest = Some(counter);
+            }
+        }
+        true
+    }
+}
+
+struct KeySlot<const PEERS: usize> {
+    id: u8,
+    cipher: ChaCha20Poly1305,
+    windows: [Option<(u8, ReplayWindow)>; PEERS],
+}
+
+impl<const PEERS: usize> KeySlot<PEERS> {
+    fn new(id: u8, key: [u8; 32]) -> Self {
+        Self {
+            id,
+            cipher: ChaCha20Poly1305::new(&Key::from(key)),
+            windows: [None; PEERS],
+        }
+    }
+
+    /// Returns the replay window of the given sender, if it has been seen before
+    fn window(&mut self, sender: u8) -> Option<&mut ReplayWindow> {
+        self.windows
+            .iter_mut()
+            .flatten()
+            .find(|(peer, _)| *peer == sender)
+            .map(|(_, window)| window)
+    }
+}
+
+fn nonce(sender: u8, key_id: u8, counter: u32) -> Nonce {
+    let mut nonce = [0; 12];
+    nonce[0] = sender;
+    nonce[1] = key_id;
+    nonce[2..6].copy_from_slice(&counter.to_le_bytes());
+    Nonce::from(nonce)
+}
+
+/// An encrypted and authenticated link with fixed `N` byte frames, receiving from up to
+/// `PEERS` other nodes
+///
+/// Refer to the [module level documentation](self) for an overview of the protocol.
+pub struct SecureLink<const N: usize, const PEERS: usize = 4> {
+    node_id: u8,
+    counter: Option<u32>,
+    current: KeySlot<PEERS>,
+    previous: Option<KeySlot<PEERS>>,
+}
+
+impl<const N: usize, const PEERS: usize> SecureLink<N, PEERS> {
+    /// The largest payload that fits into a single frame
+    pub const MAX_PAYLOAD: usize = N - HEADER_LEN - TAG_LEN;
+
+    /// Constructs a new [`SecureLink`] for the node with the given id, using the given key
+    pub fn new(node_id: u8, key_id: u8, key: [u8; 32]) -> Self {
+        Self {
+            node_id,
+            counter: Some(0),
+            current: KeySlot::new(key_id, key),
+            previous: None,
+        }
+    }
+
+    /// Returns the id of the key frames are currently sent with
+    pub fn key_id(&self) -> u8 {
+        self.current.id
+    }
+
+    /// Returns the counter the next frame will be sent with, or `None` if the counter is
+    /// exhausted
+    pub fn counter(&self) -> Option<u32> {
+        self.counter
+    }
+
+    /// Restores the counter the next frame will be sent with, for example after a reset
+    pub fn set_counter(&mut self, counter: u32) {
+        self.counter = Some(counter);
+    }
+
+    /// Switches to a new key, restarting the frame counter
+    ///
+    /// Frames sealed with the previous key are still accepted until the key is rotated
+    /// again, giving the peer time to switch over as well. `key_id` must differ from the
+    /// id of the current key.
+    pub fn rotate_key(&mut self, key_id: u8, key: [u8; 32]) {
+        let previous = core::mem::replace(&mut self.current, KeySlot::new(key_id, key));
+        self.previous = Some(previous);
+        self.counter = Some(0);
+    }
+
+    /// Seals the payload into a frame with the next counter
+    fn seal<E>(&mut self, payload: &[u8]) -> Result<[u8; N], SecureError<E>> {
+        if payload.len() > Self::MAX_PAYLOAD || payload.len() > u8::MAX as usize {
+            return Err(SecureError::PayloadTooLarge);
+        }
+        let counter = self.counter.ok_or(SecureError::CounterExhausted)?;
+
+        let mut frame = [0; N];
+        frame[0] = self.node_id;
+        frame[1] = self.current.id;
+        frame[2..6].copy_from_slice(&counter.to_le_bytes());
+        frame[6] = payload.len() as u8;
+
+        let (header, rest) = frame.split_at_mut(HEADER_LEN);
+        let (body, tag) = rest.split_at_mut(Self::MAX_PAYLOAD);
+        body[..payload.len()].copy_from_slice(payload);
+        let sealed = self
+            .current
+            .cipher
+            .encrypt_in_place_detached(&nonce(self.node_id, self.current.id, counter), header, body)
+            .map_err(|_| SecureError::EncryptionFailed)?;
+        tag.copy_from_slice(&sealed);
+
+        self.counter = counter.checked_add(1);
+        Ok(frame)
+    }
+
+    /// Authenticates and decrypts the frame in place, writing its payload into `buf` and
+    /// returning the payload length
+    fn open<E>(&mut self, frame: &mut [u8; N], buf: &mut [u8]) -> Result<usize, SecureError<E>> {
+        let sender = frame[0];
+        let key_id = frame[1];
+        let counter = u32::from_le_bytes([frame[2], frame[3], frame[4], frame[5]]);
+        let len = frame[6] as usize;
+
+        // Frames claiming to be from this node can only be reflections of its own frames
+        if sender == self.node_id {
+            return Err(SecureError::Replay);
+        }
+        if len > Self::MAX_PAYLOAD {
+            return Err(SecureError::Malformed);
+        }
+
+        let slot = match &mut self.previous {
+            _ if self.current.id == key_id => &mut self.current,
+            Some(previous) if previous.id == key_id => previous,
+            _ => return Err(SecureError::UnknownKey),
+        };
+        if slot
+            .window(sender)
+            .is_some_and(|window| !window.check(counter))
+        {
+            return Err(SecureError::Replay);
+        }
+
+        let (header, rest) = frame.split_at_mut(HEADER_LEN);
+        let (body, tag) = rest.split_at_mut(Self::MAX_PAYLOAD);
+        slot.cipher
+            .decrypt_in_place_detached(
+                &nonce(sender, key_id, counter),
+                header,
+                body,
+                Tag::from_slice(tag),
+            )
+            .map_err(|_| SecureError::AuthenticationFailed)?;
+
+        let dest = buf.get_mut(..len).ok_or(SecureError::BufferTooSmall)?;
+        let window = match slot.window(sender) {
+            Some(window) => window,
+            None => {
+                let (_, window) = slot
+                    .windows
+                    .iter_mut()
+                    .find(|entry| entry.is_none())
+                    .ok_or(SecureError::TooManyPeers)?
+                    .insert((sender, ReplayWindow::new()));
+                window
+            }
+        };
+        window.accept(counter);
+        dest.copy_from_slice(&body[..len]);
+        Ok(len)
+    }
+
+    /// Seals and transmits the payload
+    #[maybe_async::maybe_async]
+    pub async fn send<SPI: SpiDevice, D: DelayNs>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        payload: &[u8],
+    ) -> Result<(), SecureError<SPI::Error>> {
+        let frame = self.seal(payload)?;
+        radio.transmit(&frame, delay).await?;
+        Ok(())
+    }
+
+    /// Waits up to `timeout_us` microseconds for a frame from the peer, writing its
+    /// decrypted payload into the provided buffer and returning the payload length
+    ///
+    /// Frames that fail authentication, were already received or were sealed with an
+    /// unknown key are reported through their own [`SecureError`] variant, so callers can
+    /// tell an attack apart from a noisy channel. Authentic frames from a new sender are
+    /// rejected with [`SecureError::TooManyPeers`] once `PEERS` senders have been seen
+    /// under the key.
+    #[maybe_async::maybe_async]
+    pub async fn recv<SPI: SpiDevice, D: DelayNs>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        buf: &mut [u8],
+        timeout_us: u32,
+    ) -> Result<usize, SecureError<SPI::Error>> {
+        let mut frame = [0; N];
+        radio.receive(&mut frame, delay, timeout_us).await?;
+        self.open(&mut frame, buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const KEY: [u8; 32] = [0x42; 32];
+
+    fn pair() -> (SecureLink<32>, SecureLink<32>) {
+        (SecureLink::new(1, 0, KEY), SecureLink::new(2, 0, KEY))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (mut alice, mut bob) = pair();
+        assert_eq!(SecureLink::<32>::MAX_PAYLOAD, 9);
+
+        let mut frame = alice.seal::<()>(b"hello").unwrap();
+        assert_eq!(&frame[..HEADER_LEN], &[1, 0, 0, 0, 0, 0, 5]);
+        assert!(!frame.windows(5).any(|window| window == b"hello"));
+        assert_eq!(alice.counter(), Some(1));
+
+        let mut buf = [0; 9];
+        assert_eq!(bob.open::<()>(&mut frame, &mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"hello");
+
+        assert_eq!(
+            alice.seal::<()>(&[0; 10]),
+            Err(SecureError::PayloadTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_tampering() {
+        let (mut alice, mut bob) = pair();
+        let mut buf = [0; 9];
+
+        // Both the encrypted body and the authenticated header are protected
+        for pos in [HEADER_LEN + 1, 2, 31] {
+            let mut frame = alice.seal::<()>(b"hello").unwrap();
+            frame[pos] ^= 0x01;
+            assert_eq!(
+                bob.open::<()>(&mut frame, &mut buf),
+                Err(SecureError::AuthenticationFailed)
+            );
+        }
+
+        let mut frame = alice.seal::<()>(b"hello").unwrap();
+        frame[6] = 10;
+        assert_eq!(
+            bob.open::<()>(&mut frame, &mut buf),
+            Err(SecureError::Malformed)
+        );
+
+        let mut eve = SecureLink::<32>::new(3, 0, [0x24; 32]);
+        let mut frame = eve.seal::<()>(b"hello").unwrap();
+        assert_eq!(
+            bob.open::<()>(&mut frame, &mut buf),
+            Err(SecureError::AuthenticationFailed)
+        );
+    }
+
+    #[test]
+    fn test_replay() {
+        let (mut alice, mut bob) = pair();
+        let mut buf = [0; 9];
+
+        let frame = alice.seal::<()>(b"hello").unwrap();
+        assert_eq!(bob.open::<()>(&mut frame.clone(), &mut buf), Ok(5));
+        assert_eq!(
+            bob.open::<()>(&mut frame.clone(), &mut buf),
+            Err(SecureError::Replay)
+        );
+
+        // Reflecting a frame back to its sender is rejected as well
+        assert_eq!(
+            alice.open::<()>(&mut frame.clone(), &mut buf),
+            Err(SecureError::Replay)
+        );
+
+        alice.set_counter(u32::MAX);
+        alice.seal::<()>(b"last").unwrap();
+        assert_eq!(
+            alice.seal::<()>(b"hello"),
+            Err(SecureError::CounterExhausted)
+        );
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let (mut alice, mut bob) = pair();
+        let mut buf = [0; 9];
+
+        let mut old = alice.seal::<()>(b"old").unwrap();
+        alice.rotate_key(1, [0x11; 32]);
+        assert_eq!(alice.key_id(), 1);
+        assert_eq!(alice.counter(), Some(0));
+        let mut new = alice.seal::<()>(b"new").unwrap();
+
+        assert_eq!(
+            bob.open::<()>(&mut new.clone(), &mut buf),
+            Err(SecureError::UnknownKey)
+        );
+        bob.rotate_key(1, [0x11; 32]);
+        assert_eq!(bob.open::<()>(&mut new, &mut buf), Ok(3));
+        assert_eq!(bob.open::<()>(&mut old.clone(), &mut buf), Ok(3));
+        assert_eq!(&buf[..3], b"old");
+
+        bob.rotate_key(2, [0x22; 32]);
+        assert_eq!(
+            bob.open::<()>(&mut old, &mut buf),
+            Err(SecureError::UnknownKey)
+        );
+    }
+
+    #[test]
+    fn test_replay_window() {
+        let mut window = ReplayWindow::new();
+        assert!(window.accept(10));
+        assert!(window.accept(5));
+        assert!(!window.accept(5));
+        assert!(!window.accept(10));
+        assert!(window.accept(100));
+        assert!(!window.check(36));
+        assert!(window.check(37));
+        assert!(window.accept(37));
+        assert!(!window.check(37));
+        assert!(window.accept(u32::MAX));
+        assert!(!window.check(100));
+    }
+
+    #[test]
+    fn test_replay_per_sender() {
+        let (mut alice, mut bob) = pair();
+        let mut carol = SecureLink::<32>::new(3, 0, KEY);
+        let mut buf = [0; 9];
+
+        // Both senders start counting from zero, and neither replays the other
+        let mut from_alice = alice.seal::<()>(b"alice").unwrap();
+        let mut from_carol = carol.seal::<()>(b"carol").unwrap();
+        assert_eq!(bob.open::<()>(&mut from_alice.clone(), &mut buf), Ok(5));
+        assert_eq!(bob.open::<()>(&mut from_carol.clone(), &mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"carol");
+        assert_eq!(
+            bob.open::<()>(&mut from_alice, &mut buf),
+            Err(SecureError::Replay)
+        );
+        assert_eq!(
+            bob.open::<()>(&mut from_carol, &mut buf),
+            Err(SecureError::Replay)
+        );
+
+        // Authentic frames from more senders than there are windows are rejected
+        let mut bob = SecureLink::<32, 1>::new(2, 0, KEY);
+        assert_eq!(
+            bob.open::<()>(&mut alice.seal::<()>(&[]).unwrap(), &mut buf),
+            Ok(0)
+        );
+        assert_eq!(
+            bob.open::<()>(&mut carol.seal::<()>(&[]).unwrap(), &mut buf),
+            Err(SecureError::TooManyPeers)
+        );
+
+        // Forged frames do not take up a window
+        let mut bob = SecureLink::<32, 1>::new(2, 0, KEY);
+        let mut eve = SecureLink::<32>::new(4, 0, [0x24; 32]);
+        assert_eq!(
+            bob.open::<()>(&mut eve.seal::<()>(&[]).unwrap(), &mut buf),
+            Err(SecureError::AuthenticationFailed)
+        );
+        assert_eq!(
+            bob.open::<()>(&mut carol.seal::<()>(&[]).unwrap(), &mut buf),
+            Ok(0)
+        );
+    }
+}
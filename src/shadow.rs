@@ -0,0 +1,176 @@
+//! A cache of the values last written to the registers of the A7105
+//!
+//! Most registers of the A7105 are write only, so changing a single field of one means
+//! either remembering the whole value or overwriting the other fields with their defaults.
+//! Once enabled through [`A7105::enable_shadow`](crate::A7105::enable_shadow), the driver
+//! keeps a copy of every register written through
+//! [`A7105::write_reg`](crate::A7105::write_reg), which allows
+//! [`A7105::modify_reg`](crate::A7105::modify_reg) to change a single field without reading
+//! the register, and [`A7105::resync`](crate::A7105::resync) to restore the whole
+//! configuration after a reset or brownout.
+//!
+//! Resetting the A7105 through [`Command::Reset`](crate::commands::Command::Reset) keeps the
+//! cached values for [`A7105::resync`](crate::A7105::resync), but marks them as
+//! [stale](ShadowRegisters::is_stale) until they have been written back.
+//!
+//! ```ignore
+//! use a7105::prelude::*;
+//!
+//! # let a7105_spi_peripheral = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//! radio.enable_shadow();
+//!
+//! radio.write_reg(registers::Code1::default()).await.unwrap();
+//! radio
+//!     .modify_reg(|code1: &mut registers::Code1| code1.crc_enabled = false)
+//!     .await
+//!     .unwrap();
+//!
+//! radio.command(Command::Reset).await.unwrap();
+//! radio.resync().await.unwrap();
+//! ```
+
+use crate::registers::*;
+use defmt::Format;
+
+/// The values last written to each register of the A7105
+///
+/// [`CalibrationControl`] and the reset command are not cached, as writing them again
+/// would start a calibration or reset the A7105.
+#[derive(Format, PartialEq, Debug, Copy, Clone, Default)]
+pub struct ShadowRegisters {
+    pub(crate) mode_control: Option<ModeControl>,
+    pub(crate) fifo1: Option<Fifo1>,
+    pub(crate) fifo2: Option<Fifo2>,
+    pub(crate) id_data: Option<IdData>,
+    pub(crate) rc_osc3: Option<RcOsc3>,
+    pub(crate) cko_pin_control: Option<CkoPinControl>,
+    pub(crate) gpio1_pin_control: Option<Gpio1PinControl>,
+    pub(crate) gpio2_pin_control: Option<Gpio2PinControl>,
+    pub(crate) clock: Option<Clock>,
+    pub(crate) data_rate: Option<DataRate>,
+    pub(crate) pll1: Option<Pll1>,
+    pub(crate) pll2: Option<Pll2>,
+    pub(crate) pll3: Option<Pll3>,
+    pub(crate) pll4: Option<Pll4>,
+    pub(crate) pll5: Option<Pll5>,
+    pub(crate) tx1: Option<Tx1>,
+    pub(crate) tx2: Option<Tx2>,
+    pub(crate) delay1: Option<Delay1>,
+    pub(crate) delay2: Option<Delay2>,
+    pub(crate) rx: Option<Rx>,
+    pub(crate) rx_gain1: Option<RxGain1>,
+    pub(crate) rx_gain2: Option<RxGain2>,
+    pub(crate) rx_gain3: Option<RxGain3>,
+    pub(crate) rx_gain4: Option<RxGain4>,
+    pub(crate) rssi_carrier_detect_threshold: Option<RssiCarrierDetectThreshold>,
+    pub(crate) adc_control: Option<AdcControl>,
+    pub(crate) code1: Option<Code1>,
+    pub(crate) code2: Option<Code2>,
+    pub(crate) code3: Option<Code3>,
+    pub(crate) if_calibration1_config: Option<IfCalibration1Config>,
+    pub(crate) vco_current_calibration: Option<VcoCurrentCalibration>,
+    pub(crate) vco_single_band_calibration1: Option<VcoSingleBandCalibration1>,
+    pub(crate) vco_single_band_calibration2: Option<VcoSingleBandCalibration2>,
+    pub(crate) battery_detect_config: Option<BatteryDetectConfig>,
+    pub(crate) tx_test: Option<TxTest>,
+    pub(crate) stale: bool,
+}
+
+impl ShadowRegisters {
+    /// Constructs a new, empty [`ShadowRegisters`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value last written to the register, or `None` if it has not been written
+    /// or is not cached
+    ///
+    /// After a reset the value is still returned, even though the A7105 no longer holds it,
+    /// until the cache is written back through [`A7105::resync`](crate::A7105::resync).
+    pub fn get<const N: usize, R: WritableRegister<N> + Copy>(&self) -> Option<R> {
+        R::shadow_ref(self).and_then(|slot| *slot)
+    }
+
+    /// Returns `true` if the A7105 has been reset since the cached values were last written
+    /// back through [`A7105::resync`](crate::A7105::resync), so they no longer match the
+    /// registers of the A7105
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Records a value written to the register
+    pub fn set<const N: usize, R: WritableRegister<N>>(&mut self, reg: R) {
+        if let Some(slot) = R::shadow_slot(self) {
+            *slot = Some(reg);
+        }
+    }
+
+    /// Forgets all cached values
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commands::Command,
+        mock::{run, MockSpi},
+        A7105,
+    };
+
+    #[test]
+    fn test_shadow_registers() {
+        let mut shadow = ShadowRegisters::new();
+        assert_eq!(shadow.get::<1, Code1>(), None);
+
+        let code1 = Code1 {
+            crc_enabled: false,
+            ..Default::default()
+        };
+        shadow.set(code1);
+        shadow.set(IdData { id: 0x5475C52A });
+        assert_eq!(shadow.get::<1, Code1>(), Some(code1));
+        assert_eq!(shadow.get::<4, IdData>(), Some(IdData { id: 0x5475C52A }));
+
+        // Writes that would trigger an action are never cached
+        shadow.set(CalibrationControl::default());
+        assert_eq!(shadow.get::<1, CalibrationControl>(), None);
+
+        shadow.clear();
+        assert_eq!(shadow, ShadowRegisters::default());
+    }
+
+    #[test]
+    fn test_reset_invalidates_shadow() {
+        let spi = MockSpi::new();
+        let mut radio = A7105::new(spi.clone());
+        radio.enable_shadow();
+
+        let code1 = Code1 {
+            crc_enabled: false,
+            fec_enabled: true,
+            ..Default::default()
+        };
+        run!(radio.write_reg(code1)).unwrap();
+        run!(radio.command(Command::Reset)).unwrap();
+        let shadow = radio.shadow().unwrap();
+        assert!(shadow.is_stale());
+        assert_eq!(shadow.get::<1, Code1>(), Some(code1));
+
+        // The A7105 holds the reset value, so modifying starts from the default
+        run!(radio.modify_reg(|code1: &mut Code1| code1.data_whitening_enabled = true)).unwrap();
+        let expected = Code1 {
+            data_whitening_enabled: true,
+            ..Default::default()
+        };
+        assert_eq!(radio.shadow().unwrap().get::<1, Code1>(), Some(expected));
+
+        run!(radio.resync()).unwrap();
+        assert!(!radio.shadow().unwrap().is_stale());
+        let written = [Code1::id(), u8::from(expected)];
+        assert_eq!(spi.sim().transactions.last().unwrap()[..], written);
+    }
+}
@@ -0,0 +1,434 @@
+//! A snapshot of the whole register file of the A7105, for debugging
+//!
+//! [`A7105::dump_registers`](crate::A7105::dump_registers) reads every register address into
+//! a [`RegisterSnapshot`]. Registers with a readable register type are decoded, and the raw
+//! byte read back from every address is kept as well. The datasheet does not document what
+//! reading a write only address returns, so those bytes are only kept for inspection.
+//! Snapshots taken from a working and a failing unit, or before and after a change, can be
+//! compared field by field with [`RegisterSnapshot::diff`].
+//!
+//! ```ignore
+//! use a7105::prelude::*;
+//!
+//! # let (a7105_spi_peripheral, good) = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//!
+//! let snapshot = radio.dump_registers().await.unwrap();
+//! defmt::info!("{}", snapshot);
+//! for change in snapshot.diff(&good) {
+//!     defmt::info!("{}", change);
+//! }
+//! ```
+
+use crate::registers::*;
+use defmt::Format;
+
+/// The number of register addresses on the A7105
+pub const REGISTER_COUNT: usize = 0x33;
+
+/// The address of the FIFO data register, which is never read as doing so consumes data
+pub(crate) const FIFO_ADDRESS: u8 = 0x05;
+
+/// The names of the registers at each address, as given in the datasheet
+const NAMES: [&str; REGISTER_COUNT] = [
+    "Mode",
+    "Mode Control",
+    "Calibration Control",
+    "FIFO I",
+    "FIFO II",
+    "FIFO DATA",
+    "ID DATA",
+    "RC OSC I",
+    "RC OSC II",
+    "RC OSC III",
+    "CKO Pin Control",
+    "GIO1 Pin Control I",
+    "GIO2 Pin Control II",
+    "Clock",
+    "Data Rate",
+    "PLL I",
+    "PLL II",
+    "PLL III",
+    "PLL IV",
+    "PLL V",
+    "TX I",
+    "TX II",
+    "Delay I",
+    "Delay II",
+    "RX",
+    "RX Gain I",
+    "RX Gain II",
+    "RX Gain III",
+    "RX Gain IV",
+    "RSSI Threshold",
+    "ADC Control",
+    "Code I",
+    "Code II",
+    "Code III",
+    "IF Calibration I",
+    "IF Calibration II",
+    "VCO current Calibration",
+    "VCO Single band Calibration I",
+    "VCO Single band Calibration II",
+    "Battery detect",
+    "TX test",
+    "Rx DEM test I",
+    "Rx DEM test II",
+    "Charge Pump Current",
+    "Crystal test",
+    "PLL test",
+    "VCO test I",
+    "VCO test II",
+    "IFAT",
+    "RScale",
+    "Filter test",
+];
+
+/// Returns the datasheet name of the register at the given address
+pub fn register_name(address: u8) -> Option<&'static str> {
+    NAMES.get(address as usize).copied()
+}
+
+/// The fields of every register decoded by a [`RegisterSnapshot`] as
+/// `(address, field name, mask)`, in address order and from the most significant bit
+const FIELDS: [(u8, &str, u8); 42] = [
+    (0x00, "fec_pass", 0b0100_0000),
+    (0x00, "crc_pass", 0b0010_0000),
+    (0x00, "rf_enabled", 0b0001_0000),
+    (0x00, "internal_crystal_enabled", 0b0000_1000),
+    (0x00, "pll_enabled", 0b0000_0100),
+    (0x00, "trx_enabled", 0b0000_0010),
+    (0x00, "trx_status", 0b0000_0001),
+    (0x01, "direct_data_pin_output", 0b1000_0000),
+    (0x01, "auto_rssi", 0b0100_0000),
+    (0x01, "auto_if", 0b0010_0000),
+    (0x01, "cd_filter", 0b0001_0000),
+    (0x01, "data_mode", 0b0000_0010),
+    (0x01, "adc_measurement_enabled", 0b0000_0001),
+    (0x02, "vco_current_calibration_enabled", 0b0000_0100),
+    (0x02, "vco_bank_calibration_enabled", 0b0000_0010),
+    (0x02, "if_filter_bank_calibration_enabled", 0b0000_0001),
+    (0x0D, "clock_generation_ref_cnt", 0b1111_0000),
+    (0x0D, "sys_clock_div", 0b0000_1100),
+    (0x0D, "clock_generated_enabled", 0b0000_0010),
+    (0x0D, "external_crystal_osc", 0b0000_0001),
+    (0x0E, "rate", 0b1111_1111),
+    (0x0F, "channel", 0b1111_1111),
+    (0x10, "crystal_freq_doubler", 0b1000_0000),
+    (0x10, "rf_pll_ref_counter", 0b0110_0000),
+    (0x10, "pll_chn_step", 0b0001_1110),
+    (0x10, "ip8", 0b0000_0001),
+    (0x11, "bip", 0b1111_1111),
+    (0x12, "bfp", 0b1111_1111),
+    (0x13, "bfp", 0b1111_1111),
+    (0x19, "manual_vga_calibration", 0b1000_0000),
+    (0x19, "mixer_gain", 0b0001_1000),
+    (0x19, "lna_gain", 0b0000_0111),
+    (0x1D, "voltage", 0b1111_1111),
+    (0x22, "calibration_successful", 0b0001_0000),
+    (0x22, "calibration_value", 0b0000_1111),
+    (0x23, "deviation", 0b0001_1111),
+    (0x24, "success", 0b0001_0000),
+    (0x24, "value", 0b0000_1111),
+    (0x25, "voltage_output", 0b0011_0000),
+    (0x25, "success", 0b0000_1000),
+    (0x25, "value", 0b0000_0111),
+    (0x27, "voltage_above_threshold", 0b0001_0000),
+];
+
+/// A field whose value differs between two [`RegisterSnapshot`]s
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub enum RegisterChange {
+    /// A field of a decoded register changed
+    Field {
+        /// The address of the register
+        address: u8,
+        /// The datasheet name of the register
+        register: &'static str,
+        /// The name of the field in the register type
+        field: &'static str,
+        /// The raw value of the field in the snapshot `diff` was called on, shifted down to
+        /// bit 0
+        before: u8,
+        /// The raw value of the field in the snapshot passed to `diff`, shifted down to bit 0
+        after: u8,
+    },
+    /// The ID register changed
+    Id {
+        /// The ID in the snapshot `diff` was called on
+        before: IdData,
+        /// The ID in the snapshot passed to `diff`
+        after: IdData,
+    },
+}
+
+/// The state of the A7105 register file at a point in time
+///
+/// Refer to the [module level documentation](self) for an overview.
+#[derive(Format, PartialEq, Debug, Clone)]
+pub struct RegisterSnapshot {
+    mode: Mode,
+    mode_control: ModeControl,
+    calibration_control: CalibrationControl,
+    id: IdData,
+    clock: Clock,
+    data_rate: DataRate,
+    pll1: Pll1,
+    pll2: Pll2,
+    pll3: Pll3,
+    pll4: Pll4,
+    pll5: Pll5,
+    rx_gain1: RxGain1,
+    rssi: RssiAdcOutput,
+    if_calibration1: IfCalibration1Result,
+    if_calibration2: IfCalibration2,
+    vco_current_calibration: VcoCurrentCalibrationResult,
+    vco_single_band_calibration: VcoSingleBandCalibration1Result,
+    battery_detect: BatteryDetectResult,
+    raw: [u8; REGISTER_COUNT],
+}
+
+impl RegisterSnapshot {
+    /// Constructs a snapshot from the byte read from every address and the ID register
+    ///
+    /// The bytes at the FIFO and ID addresses are ignored.
+    pub fn from_raw(mut raw: [u8; REGISTER_COUNT], id: IdData) -> Self {
+        raw[FIFO_ADDRESS as usize] = 0;
+        raw[IdData::id() as usize] = 0;
+        let reg = |id: u8| raw[id as usize];
+
+        Self {
+            mode: reg(Mode::id()).into(),
+            mode_control: reg(ModeControl::id()).into(),
+            calibration_control: reg(CalibrationControl::id()).into(),
+            id,
+            clock: reg(Clock::id()).into(),
+            data_rate: reg(DataRate::id()).into(),
+            pll1: reg(Pll1::id()).into(),
+            pll2: reg(Pll2::id()).into(),
+            pll3: reg(Pll3::id()).into(),
+            pll4: reg(Pll4::id()).into(),
+            pll5: reg(Pll5::id()).into(),
+            rx_gain1: reg(RxGain1::id()).into(),
+            rssi: reg(RssiAdcOutput::id()).into(),
+            if_calibration1: reg(IfCalibration1Result::id()).into(),
+            if_calibration2: reg(IfCalibration2::id()).into(),
+            vco_current_calibration: reg(VcoCurrentCalibrationResult::id()).into(),
+            vco_single_band_calibration: reg(VcoSingleBandCalibration1Result::id()).into(),
+            battery_detect: reg(BatteryDetectResult::id()).into(),
+            raw,
+        }
+    }
+
+    /// Returns the byte read from the given address, or `None` for the FIFO and ID
+    /// addresses and addresses past the end of the register file
+    pub fn raw(&self, address: u8) -> Option<u8> {
+        match address {
+            FIFO_ADDRESS => None,
+            address if address == IdData::id() => None,
+            address => self.raw.get(address as usize).copied(),
+        }
+    }
+
+    /// Returns the decoded [`Mode`] register
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Returns the decoded [`ModeControl`] register
+    pub fn mode_control(&self) -> ModeControl {
+        self.mode_control
+    }
+
+    /// Returns the decoded [`CalibrationControl`] register
+    pub fn calibration_control(&self) -> CalibrationControl {
+        self.calibration_control
+    }
+
+    /// Returns the [`IdData`] register
+    pub fn id(&self) -> IdData {
+        self.id
+    }
+
+    /// Returns the decoded [`Clock`] register
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    /// Returns the decoded [`DataRate`] register
+    pub fn data_rate(&self) -> DataRate {
+        self.data_rate
+    }
+
+    /// Returns the decoded [`Pll1`] register
+    pub fn pll1(&self) -> Pll1 {
+        self.pll1
+    }
+
+    /// Returns the decoded [`Pll2`] register
+    pub fn pll2(&self) -> Pll2 {
+        self.pll2
+    }
+
+    /// Returns the decoded [`Pll3`] register
+    pub fn pll3(&self) -> Pll3 {
+        self.pll3
+    }
+
+    /// Returns the decoded [`Pll4`] register
+    pub fn pll4(&self) -> Pll4 {
+        self.pll4
+    }
+
+    /// Returns the decoded [`Pll5`] register
+    pub fn pll5(&self) -> Pll5 {
+        self.pll5
+    }
+
+    /// Returns the decoded [`RxGain1`] register
+    pub fn rx_gain1(&self) -> RxGain1 {
+        self.rx_gain1
+    }
+
+    /// Returns the decoded [`RssiAdcOutput`] register
+    pub fn rssi(&self) -> RssiAdcOutput {
+        self.rssi
+    }
+
+    /// Returns the decoded [`IfCalibration1Result`] register
+    pub fn if_calibration1(&self) -> IfCalibration1Result {
+        self.if_calibration1
+    }
+
+    /// Returns the decoded [`IfCalibration2`] register
+    pub fn if_calibration2(&self) -> IfCalibration2 {
+        self.if_calibration2
+    }
+
+    /// Returns the decoded [`VcoCurrentCalibrationResult`] register
+    pub fn vco_current_calibration(&self) -> VcoCurrentCalibrationResult {
+        self.vco_current_calibration
+    }
+
+    /// Returns the decoded [`VcoSingleBandCalibration1Result`] register
+    pub fn vco_single_band_calibration(&self) -> VcoSingleBandCalibration1Result {
+        self.vco_single_band_calibration
+    }
+
+    /// Returns the decoded [`BatteryDetectResult`] register
+    pub fn battery_detect(&self) -> BatteryDetectResult {
+        self.battery_detect
+    }
+
+    /// Lists the fields of the decoded registers whose values differ from `other`, in
+    /// address order
+    ///
+    /// Only the ID and the registers decoded by the snapshot are compared, as the bytes read
+    /// from write only addresses are undocumented.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = RegisterChange> + 'a {
+        let id = (self.id != other.id).then_some(RegisterChange::Id {
+            before: self.id,
+            after: other.id,
+        });
+        let fields = FIELDS.iter().filter_map(|&(address, field, mask)| {
+            let value = |raw: &[u8; REGISTER_COUNT]| {
+                (raw[address as usize] & mask) >> mask.trailing_zeros()
+            };
+            let before = value(&self.raw);
+            let after = value(&other.raw);
+            (before != after).then_some(RegisterChange::Field {
+                address,
+                register: NAMES[address as usize],
+                field,
+                before,
+                after,
+            })
+        });
+        id.into_iter().chain(fields)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_raw() {
+        let mut raw = [0; REGISTER_COUNT];
+        raw[DataRate::id() as usize] = 9;
+        raw[FIFO_ADDRESS as usize] = 0xFF;
+        let snapshot = RegisterSnapshot::from_raw(raw, IdData { id: 0x5475C52A });
+
+        assert_eq!(snapshot.data_rate(), DataRate { rate: 9 });
+        assert_eq!(snapshot.raw(DataRate::id()), Some(9));
+        assert_eq!(snapshot.raw(FIFO_ADDRESS), None);
+        assert_eq!(snapshot.raw(IdData::id()), None);
+        assert_eq!(snapshot.raw(REGISTER_COUNT as u8), None);
+        assert_eq!(register_name(0x32), Some("Filter test"));
+    }
+
+    #[test]
+    fn test_diff() {
+        let id = IdData { id: 0x5475C52A };
+        let mut raw = [0; REGISTER_COUNT];
+        let before = RegisterSnapshot::from_raw(raw, id);
+        assert_eq!(before.diff(&before).count(), 0);
+
+        raw[Pll1::id() as usize] = 0x10;
+        raw[Clock::id() as usize] = 0b1000_0001;
+        raw[FIFO_ADDRESS as usize] = 0x01;
+        // Write only registers are not compared
+        raw[Tx1::id() as usize] = 0x01;
+        let after = RegisterSnapshot::from_raw(raw, IdData { id: 0x12345678 });
+
+        let mut changes = before.diff(&after);
+        assert_eq!(
+            changes.next(),
+            Some(RegisterChange::Id {
+                before: id,
+                after: IdData { id: 0x12345678 },
+            })
+        );
+        assert_eq!(
+            changes.next(),
+            Some(RegisterChange::Field {
+                address: 0x0D,
+                register: "Clock",
+                field: "clock_generation_ref_cnt",
+                before: 0,
+                after: 0b1000,
+            })
+        );
+        assert_eq!(
+            changes.next(),
+            Some(RegisterChange::Field {
+                address: 0x0D,
+                register: "Clock",
+                field: "external_crystal_osc",
+                before: 0,
+                after: 1,
+            })
+        );
+        assert_eq!(
+            changes.next(),
+            Some(RegisterChange::Field {
+                address: 0x0F,
+                register: "PLL I",
+                field: "channel",
+                before: 0x00,
+                after: 0x10,
+            })
+        );
+        assert_eq!(changes.next(), None);
+    }
+
+    #[test]
+    fn test_fields() {
+        for pair in FIELDS.windows(2) {
+            let ((address, _, mask), (next_address, _, next_mask)) = (pair[0], pair[1]);
+            assert!(address <= next_address);
+            // Fields of a register are listed from the MSB and never overlap
+            assert!(address < next_address || mask > next_mask && mask & next_mask == 0);
+        }
+    }
+}
@@ -0,0 +1,539 @@
+//! A byte stream over packets, for using a pair of A7105s as a serial link
+//!
+//! [`A7105Stream`] implements the `embedded-io` `Read` and `Write` traits, or their
+//! `embedded-io-async` counterparts with the `async` feature, on top of
+//! [`A7105::transmit`] and [`A7105::receive`]. Written bytes are collected into fixed `N`
+//! byte packets, matching the FIFO length configured through
+//! [`Fifo1`](crate::registers::Fifo1), which carry a 2 byte header of a sequence number and
+//! payload length.
+//!
+//! A packet is sent once it is full, when the stream is flushed, or before the stream waits
+//! to read, so that a request written before reading a response is never held back. A
+//! partly filled packet is also sent by the first write made once its oldest byte has
+//! waited `flush_us` microseconds, as measured by a [`Monotonic`] clock. The stream has no
+//! task of its own, so a writer that stops part way through a packet must still flush the
+//! stream for the remaining bytes to be sent.
+//! Received packets are delivered in sequence number order. Packets arriving ahead of a
+//! lost packet are held back, up to `W` of them, until either the missing packet arrives or
+//! the stream times out waiting for it and skips ahead. Duplicate packets are dropped.
+//!
+//! Packets are not acknowledged, so bytes in a lost packet are lost, and both ends must be
+//! started together so that their sequence numbers agree.
+//!
+//! ```ignore
+//! use a7105::prelude::*;
+//! use a7105::stream::A7105Stream;
+//! use embedded_io_async::{Read, Write};
+//!
+//! # let (a7105_spi_peripheral, delay, clock) = unimplemented!();
+//! let radio = A7105::new(a7105_spi_peripheral);
+//! let mut stream: A7105Stream<_, _, _, 16> = A7105Stream::new(radio, delay, clock, 10_000, 5_000);
+//!
+//! stream.write_all(b"ping").await.unwrap();
+//! let mut buf = [0; 4];
+//! stream.read_exact(&mut buf).await.unwrap();
+//! ```
+
+use crate::{time::Monotonic, ReadPacketError, StreamError, A7105};
+
+#[cfg(feature = "blocking")]
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs, spi::SpiDevice};
+
+const HEADER_LEN: usize = 2;
+
+/// Where a received sequence number falls relative to the next expected one
+#[derive(PartialEq, Debug, Copy, Clone)]
+enum Arrival {
+    /// The packet is the next one expected
+    Next,
+    /// The packet is ahead of the next expected one, but close enough to hold
+    Ahead,
+    /// The packet is too far ahead to hold, so the stream skips ahead to it
+    Skip,
+    /// The packet was already delivered or skipped
+    Duplicate,
+}
+
+fn arrival(expected: u8, seq: u8, window: usize) -> Arrival {
+    match seq.wrapping_sub(expected) {
+        0 => Arrival::Next,
+        offset if usize::from(offset) <= window => Arrival::Ahead,
+        offset if offset < 0x80 => Arrival::Skip,
+        _ => Arrival::Duplicate,
+    }
+}
+
+/// Returns the sequence number and payload of a packet, or `None` if it is malformed
+fn decode(packet: &[u8]) -> Option<(u8, &[u8])> {
+    let len = usize::from(*packet.get(1)?);
+    let payload = packet.get(HEADER_LEN..HEADER_LEN + len)?;
+    Some((packet[0], payload))
+}
+
+/// A byte stream over `N` byte packets, holding up to `W` packets received out of order
+///
+/// Refer to the [module level documentation](self) for an overview.
+pub struct A7105Stream<SPI, D, C, const N: usize, const W: usize = 4> {
+    radio: A7105<SPI>,
+    delay: D,
+    clock: C,
+    timeout_us: u32,
+    flush_us: u32,
+    tx: [u8; N],
+    tx_len: usize,
+    tx_seq: u8,
+    tx_since_us: u64,
+    rx: [u8; N],
+    rx_pos: usize,
+    rx_len: usize,
+    rx_seq: u8,
+    held: [Option<[u8; N]>; W],
+}
+
+impl<SPI, D, C, const N: usize, const W: usize> A7105Stream<SPI, D, C, N, W> {
+    /// The number of bytes carried by each packet
+    pub const MAX_PAYLOAD: usize = N - HEADER_LEN;
+
+    /// Fails to compile when a packet can not carry a payload, or its payload length does not
+    /// fit into the 1 byte length header
+    const PACKET_FITS: () = assert!(
+        N > HEADER_LEN && N <= HEADER_LEN + u8::MAX as usize,
+        "A7105Stream packets must be between 3 and 257 bytes"
+    );
+
+    /// Constructs a new [`A7105Stream`] over the provided radio
+    ///
+    /// Reads wait for packets in windows of `timeout_us` microseconds, and give up on a lost
+    /// packet after one window without it. Partly filled packets are sent by the first write
+    /// made `flush_us` microseconds after their first byte was written. The A7105 must
+    /// already be configured for `N` byte packets. Using packets shorter than 3 or longer
+    /// than 257 bytes is a compile time error.
+    pub fn new(radio: A7105<SPI>, delay: D, clock: C, timeout_us: u32, flush_us: u32) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::PACKET_FITS;
+        Self {
+            radio,
+            delay,
+            clock,
+            timeout_us,
+            flush_us,
+            tx: [0; N],
+            tx_len: 0,
+            tx_seq: 0,
+            tx_since_us: 0,
+            rx: [0; N],
+            rx_pos: 0,
+            rx_len: 0,
+            rx_seq: 0,
+            held: [None; W],
+        }
+    }
+
+    /// Destroys this [`A7105Stream`], returning the radio, delay and clock
+    ///
+    /// Any unsent or unread bytes are discarded.
+    pub fn release(self) -> (A7105<SPI>, D, C) {
+        (self.radio, self.delay, self.clock)
+    }
+
+    /// Copies the payload of a packet into the read buffer, advancing the expected sequence
+    fn deliver(&mut self, packet: &[u8; N]) {
+        if let Some((seq, payload)) = decode(packet) {
+            self.rx[..payload.len()].copy_from_slice(payload);
+            self.rx_pos = 0;
+            self.rx_len = payload.len();
+            self.rx_seq = seq.wrapping_add(1);
+        }
+    }
+
+    /// Delivers a held packet if it is the next one expected, returning `true` if one was
+    fn deliver_held(&mut self) -> bool {
+        let expected = self.rx_seq;
+        let next = self
+            .held
+            .iter_mut()
+            .find(|held| matches!(held, Some(packet) if packet[0] == expected));
+        match next.and_then(Option::take) {
+            Some(packet) => {
+                self.deliver(&packet);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Skips ahead to the earliest held packet, giving up on the packets before it
+    fn skip_to_held(&mut self) {
+        let expected = self.rx_seq;
+        if let Some(seq) = self
+            .held
+            .iter()
+            .flatten()
+            .map(|packet| packet[0])
+            .min_by_key(|seq| seq.wrapping_sub(expected))
+        {
+            self.rx_seq = seq;
+        }
+    }
+
+    /// Handles a received packet, delivering it or holding it back until its turn
+    ///
+    /// Must only be called once every delivered byte has been read.
+    fn accept(&mut self, packet: [u8; N]) {
+        let Some((seq, _)) = decode(&packet) else {
+            return;
+        };
+        match arrival(self.rx_seq, seq, W) {
+            Arrival::Next => self.deliver(&packet),
+            Arrival::Ahead => {
+                if self.held.iter().flatten().any(|held| held[0] == seq) {
+                    return;
+                }
+                match self.held.iter_mut().find(|slot| slot.is_none()) {
+                    Some(slot) => *slot = Some(packet),
+                    None => {
+                        // Rather than dropping the packet, skip ahead to the earliest held
+                        // packet to make room for it
+                        self.skip_to_held();
+                        self.deliver_held();
+                        self.accept(packet);
+                    }
+                }
+            }
+            Arrival::Skip => {
+                self.held = [None; W];
+                self.deliver(&packet);
+            }
+            Arrival::Duplicate => {}
+        }
+    }
+
+    /// Copies buffered received bytes into `buf`, returning the number of bytes copied
+    fn take(&mut self, buf: &mut [u8]) -> usize {
+        let len = buf.len().min(self.rx_len - self.rx_pos);
+        buf[..len].copy_from_slice(&self.rx[self.rx_pos..self.rx_pos + len]);
+        self.rx_pos += len;
+        len
+    }
+}
+
+impl<SPI, D, C, const N: usize, const W: usize> A7105Stream<SPI, D, C, N, W>
+where
+    SPI: SpiDevice,
+    D: DelayNs,
+    C: Monotonic,
+{
+    /// Sends the buffered bytes as a packet, if there are any
+    #[maybe_async::maybe_async]
+    async fn send_packet(&mut self) -> Result<(), StreamError<SPI::Error>> {
+        if self.tx_len == 0 {
+            return Ok(());
+        }
+        self.tx[0] = self.tx_seq;
+        self.tx[1] = self.tx_len as u8;
+        self.tx[HEADER_LEN + self.tx_len..].fill(0);
+        self.radio.transmit(&self.tx, &mut self.delay).await?;
+        self.tx_seq = self.tx_seq.wrapping_add(1);
+        self.tx_len = 0;
+        Ok(())
+    }
+
+    /// Buffers as much of `buf` as fits into the current packet, sending the packet once it
+    /// is full or has waited `flush_us`
+    ///
+    /// The bytes are buffered before the packet is sent, so a failed send still reports them
+    /// as written. The packet is retried, and the failure reported, by the next write, flush
+    /// or read.
+    #[maybe_async::maybe_async]
+    async fn write_inner(&mut self, buf: &[u8]) -> Result<usize, StreamError<SPI::Error>> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // A full packet is only left behind by a failed send
+        if self.tx_len == Self::MAX_PAYLOAD {
+            self.send_packet().await?;
+        }
+
+        let now_us = self.clock.now_us();
+        if self.tx_len == 0 {
+            self.tx_since_us = now_us;
+        }
+        let len = buf.len().min(Self::MAX_PAYLOAD - self.tx_len);
+        let start = HEADER_LEN + self.tx_len;
+        self.tx[start..start + len].copy_from_slice(&buf[..len]);
+        self.tx_len += len;
+
+        let waited_us = now_us.saturating_sub(self.tx_since_us);
+        if self.tx_len == Self::MAX_PAYLOAD || waited_us >= u64::from(self.flush_us) {
+            let _ = self.send_packet().await;
+        }
+        Ok(len)
+    }
+
+    #[maybe_async::maybe_async]
+    async fn read_inner(&mut self, buf: &mut [u8]) -> Result<usize, StreamError<SPI::Error>> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if self.rx_pos < self.rx_len {
+                return Ok(self.take(buf));
+            }
+            if self.deliver_held() {
+                continue;
+            }
+
+            self.send_packet().await?;
+            let mut packet = [0; N];
+            match self
+                .radio
+                .receive(&mut packet, &mut self.delay, self.timeout_us)
+                .await
+            {
+                Ok(()) => self.accept(packet),
+                Err(ReadPacketError::Timeout) => self.skip_to_held(),
+                Err(ReadPacketError::PacketError(_)) => {}
+                Err(ReadPacketError::SpiError(e)) => return Err(StreamError::SpiError(e)),
+            }
+        }
+    }
+}
+
+impl<SPI: SpiDevice, D, C, const N: usize, const W: usize> embedded_io::ErrorType
+    for A7105Stream<SPI, D, C, N, W>
+{
+    type Error = StreamError<SPI::Error>;
+}
+
+#[cfg(feature = "blocking")]
+impl<SPI: SpiDevice, D: DelayNs, C: Monotonic, const N: usize, const W: usize> embedded_io::Read
+    for A7105Stream<SPI, D, C, N, W>
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.read_inner(buf)
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<SPI: SpiDevice, D: DelayNs, C: Monotonic, const N: usize, const W: usize> embedded_io::Write
+    for A7105Stream<SPI, D, C, N, W>
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.write_inner(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.send_packet()
+    }
+}
+
+#[cfg(feature = "async")]
+impl<SPI: SpiDevice, D: DelayNs, C: Monotonic, const N: usize, const W: usize>
+    embedded_io_async::Read for A7105Stream<SPI, D, C, N, W>
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.read_inner(buf).await
+    }
+}
+
+#[cfg(feature = "async")]
+impl<SPI: SpiDevice, D: DelayNs, C: Monotonic, const N: usize, const W: usize>
+    embedded_io_async::Write for A7105Stream<SPI, D, C, N, W>
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.write_inner(buf).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.send_packet().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::{run, MockDelay, MockSpi};
+    use core::cell::Cell;
+    #[cfg(feature = "blocking")]
+    use embedded_io::{Read, Write};
+    #[cfg(feature = "async")]
+    use embedded_io_async::{Read, Write};
+
+    const FLUSH_US: u32 = 1_000;
+
+    /// A clock the test moves forward by hand
+    struct MockClock<'a>(&'a Cell<u64>);
+
+    impl Monotonic for MockClock<'_> {
+        fn now_us(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    type Endpoint<'a> = A7105Stream<MockSpi, MockDelay, MockClock<'a>, 8, 2>;
+
+    fn endpoint(clock: &Cell<u64>) -> (Endpoint<'_>, MockSpi) {
+        let spi = MockSpi::new();
+        let radio = A7105::new(spi.clone());
+        let stream = A7105Stream::new(radio, MockDelay::default(), MockClock(clock), 100, FLUSH_US);
+        (stream, spi)
+    }
+
+    /// Delivers every packet sent by one end to the other
+    fn carry(from: &MockSpi, to: &MockSpi) {
+        for packet in from.sim().sent.drain(..) {
+            to.sim().push(&packet);
+        }
+    }
+
+    fn packet(seq: u8, payload: &[u8]) -> [u8; 8] {
+        let mut packet = [0; 8];
+        packet[0] = seq;
+        packet[1] = payload.len() as u8;
+        packet[HEADER_LEN..HEADER_LEN + payload.len()].copy_from_slice(payload);
+        packet
+    }
+
+    fn stream() -> A7105Stream<(), (), (), 8, 2> {
+        A7105Stream::new(A7105::new(()), (), (), 0, 0)
+    }
+
+    fn read_all(stream: &mut A7105Stream<(), (), (), 8, 2>) -> ([u8; 16], usize) {
+        let mut buf = [0; 16];
+        let mut len = 0;
+        loop {
+            if stream.rx_pos == stream.rx_len && !stream.deliver_held() {
+                return (buf, len);
+            }
+            len += stream.take(&mut buf[len..]);
+        }
+    }
+
+    #[test]
+    fn test_arrival() {
+        assert_eq!(arrival(5, 5, 2), Arrival::Next);
+        assert_eq!(arrival(5, 7, 2), Arrival::Ahead);
+        assert_eq!(arrival(5, 8, 2), Arrival::Skip);
+        assert_eq!(arrival(5, 4, 2), Arrival::Duplicate);
+        assert_eq!(arrival(0xFF, 0x01, 2), Arrival::Ahead);
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(&packet(3, &[1, 2])), Some((3, &[1, 2][..])));
+        assert_eq!(decode(&[0, 7, 0, 0]), None);
+        assert_eq!(decode(&[0]), None);
+    }
+
+    #[test]
+    fn test_reorder() {
+        let mut stream = stream();
+        stream.accept(packet(1, b"cd"));
+        stream.accept(packet(2, b"ef"));
+        // Duplicates of held packets are dropped
+        stream.accept(packet(1, b"cd"));
+        assert_eq!(read_all(&mut stream).1, 0);
+
+        stream.accept(packet(0, b"ab"));
+        let (buf, len) = read_all(&mut stream);
+        assert_eq!(&buf[..len], b"abcdef");
+
+        // Old packets are dropped
+        stream.accept(packet(1, b"cd"));
+        assert_eq!(read_all(&mut stream).1, 0);
+    }
+
+    #[test]
+    fn test_skip_lost_packet() {
+        let mut stream = stream();
+        stream.accept(packet(2, b"ef"));
+        stream.accept(packet(1, b"cd"));
+        assert_eq!(read_all(&mut stream).1, 0);
+
+        // Packet 0 never arrives, so the stream gives up on it after a timeout
+        stream.skip_to_held();
+        let (buf, len) = read_all(&mut stream);
+        assert_eq!(&buf[..len], b"cdef");
+
+        // Packets too far ahead to hold make the stream skip ahead immediately
+        stream.accept(packet(9, b"gh"));
+        let (buf, len) = read_all(&mut stream);
+        assert_eq!(&buf[..len], b"gh");
+    }
+
+    #[test]
+    fn test_held_full() {
+        let mut stream = stream();
+        stream.accept(packet(1, b"cd"));
+        stream.accept(packet(2, b"ef"));
+        stream.accept(packet(0, b"ab"));
+        let mut buf = [0; 2];
+        assert_eq!(stream.take(&mut buf), 2);
+
+        // Packet 1 is still held, so packet 3 finds no free slot and delivers it to make room
+        stream.accept(packet(3, b"gh"));
+        let (buf, len) = read_all(&mut stream);
+        assert_eq!(&buf[..len], b"cdefgh");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let clock = Cell::new(0);
+        let (mut writer, writer_spi) = endpoint(&clock);
+        let (mut reader, reader_spi) = endpoint(&clock);
+
+        run!(writer.write_all(b"hello, world!")).unwrap();
+        // Full packets are sent as they fill, while the remainder waits for a flush
+        assert_eq!(writer_spi.sim().sent.len(), 2);
+        run!(writer.flush()).unwrap();
+        let sim = writer_spi.sim();
+        assert_eq!(sim.sent.len(), 3);
+        assert_eq!(sim.sent[0][..], *b"\x00\x06hello,");
+        assert_eq!(sim.sent[2][..], *b"\x02\x01!\0\0\0\0\0");
+        drop(sim);
+
+        carry(&writer_spi, &reader_spi);
+        let mut buf = [0; 13];
+        run!(reader.read_exact(&mut buf)).unwrap();
+        assert_eq!(&buf, b"hello, world!");
+
+        // Flushing an empty stream sends nothing
+        run!(writer.flush()).unwrap();
+        assert!(writer_spi.sim().sent.is_empty());
+    }
+
+    #[test]
+    fn test_read_sends_pending_bytes() {
+        let clock = Cell::new(0);
+        let (mut stream, spi) = endpoint(&clock);
+
+        run!(stream.write_all(b"ping")).unwrap();
+        spi.sim().push(&packet(0, b"pong"));
+        let mut buf = [0; 4];
+        run!(stream.read_exact(&mut buf)).unwrap();
+        assert_eq!(&buf, b"pong");
+        assert_eq!(spi.sim().sent[0][..], packet(0, b"ping"));
+    }
+
+    #[test]
+    fn test_timed_flush() {
+        let clock = Cell::new(0);
+        let (mut stream, spi) = endpoint(&clock);
+
+        run!(stream.write_all(b"ab")).unwrap();
+        clock.set(u64::from(FLUSH_US) - 1);
+        run!(stream.write_all(b"c")).unwrap();
+        assert!(spi.sim().sent.is_empty());
+
+        // The deadline runs from the first byte buffered, not the last
+        clock.set(u64::from(FLUSH_US));
+        run!(stream.write_all(b"d")).unwrap();
+        assert_eq!(spi.sim().sent[..], [packet(0, b"abcd")]);
+
+        // The next packet starts a new deadline
+        run!(stream.write_all(b"e")).unwrap();
+        assert_eq!(spi.sim().sent.len(), 1);
+    }
+}
@@ -0,0 +1,658 @@
+//! A time-slotted MAC synchronised by beacons from a single master
+//!
+//! Time is divided into frames of [`TdmaConfig::frame_us`] microseconds, each split into
+//! [`TdmaConfig::slot_count`] equally sized slots. Every slot has a global, ever increasing
+//! slot counter. At the start of every frame the [`TdmaMaster`] transmits a beacon on the
+//! beacon channel carrying the slot counter of that frame's first slot, which is reserved
+//! for the beacon itself.
+//!
+//! A [`TdmaNode`] timestamps each beacon it receives with its own [`Monotonic`] clock to
+//! compute when every future slot begins. Comparing the time elapsed between two beacons on
+//! both clocks yields the drift of the node's clock relative to the master, which is
+//! compensated for when predicting slot boundaries. Each slot begins with a guard time during
+//! which nobody transmits, absorbing any residual timing error.
+//!
+//! All data is exchanged on the data channel, switching [`Pll1`] back to the beacon channel
+//! only to send or receive beacons.
+//!
+//! ```ignore
+//! use a7105::prelude::*;
+//! use a7105::tdma::{TdmaConfig, TdmaNode};
+//!
+//! # let (a7105_spi_peripheral, mut delay, clock) = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//! let mut node: TdmaNode<16> = TdmaNode::new(TdmaConfig::default()).unwrap();
+//!
+//! loop {
+//!     // Wait for the next beacon, then send our data in slot 3
+//!     node.sync(&mut radio, &mut delay, &clock, 100_000).await.unwrap();
+//!     node.transmit_in_slot(&mut radio, &mut delay, &clock, 3, &[0; 16]).await.unwrap();
+//! }
+//! ```
+
+use crate::{registers::Pll1, time::Monotonic, ReadPacketError, TdmaError, A7105};
+use core::convert::Infallible;
+use defmt::Format;
+
+#[cfg(feature = "blocking")]
+use embedded_hal::{delay::DelayNs, spi::SpiDevice};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs, spi::SpiDevice};
+
+const BEACON_MAGIC: u8 = 0xBE;
+const BEACON_LEN: usize = 5;
+const PPM: i64 = 1_000_000;
+
+/// Writes a beacon carrying the given slot counter into `frame`
+fn encode_beacon(slot_counter: u32, frame: &mut [u8]) {
+    frame.fill(0);
+    frame[0] = BEACON_MAGIC;
+    frame[1..BEACON_LEN].copy_from_slice(&slot_counter.to_le_bytes());
+}
+
+/// Parses a received beacon, returning its slot counter
+fn decode_beacon(frame: &[u8]) -> Option<u32> {
+    if frame.len() < BEACON_LEN || frame[0] != BEACON_MAGIC {
+        return None;
+    }
+    let mut counter = [0; 4];
+    counter.copy_from_slice(&frame[1..BEACON_LEN]);
+    Some(u32::from_le_bytes(counter))
+}
+
+/// Configuration of the TDMA frame, which must be identical on the master and all nodes
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct TdmaConfig {
+    /// The length of a full frame, in microseconds
+    pub frame_us: u32,
+    /// The number of slots in every frame, including the beacon slot
+    pub slot_count: u16,
+    /// The time at the start of every slot during which nobody transmits, in microseconds
+    pub guard_us: u32,
+    /// The [`Pll1`] channel beacons are sent on
+    pub beacon_channel: u8,
+    /// The [`Pll1`] channel all other slots are sent on
+    pub data_channel: u8,
+    /// The time between the master starting a beacon and a node timestamping its
+    /// reception, in microseconds. This depends on the packet length and data rate.
+    pub beacon_latency_us: u32,
+}
+
+impl TdmaConfig {
+    /// Constructs a new [`TdmaConfig`] with `slot_count` slots per `frame_us` microsecond
+    /// frame, taking all other settings from [`TdmaConfig::default`]
+    ///
+    /// Returns [`TdmaError::InvalidConfig`] if the frame has no slots, or is too short to
+    /// give every slot at least a microsecond.
+    pub fn new(frame_us: u32, slot_count: u16) -> Result<Self, TdmaError<Infallible>> {
+        let config = Self {
+            frame_us,
+            slot_count,
+            ..Default::default()
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks that the frame can be divided into the configured number of slots
+    pub const fn validate(&self) -> Result<(), TdmaError<Infallible>> {
+        if self.slot_count == 0 || self.frame_us < self.slot_count as u32 {
+            return Err(TdmaError::InvalidConfig);
+        }
+        Ok(())
+    }
+
+    /// Returns the length of a single slot, in microseconds
+    pub const fn slot_us(&self) -> u32 {
+        self.frame_us / self.slot_count as u32
+    }
+}
+
+impl Default for TdmaConfig {
+    fn default() -> Self {
+        Self {
+            frame_us: 100_000,
+            slot_count: 10,
+            guard_us: 1_000,
+            beacon_channel: 0,
+            data_channel: 0,
+            beacon_latency_us: 0,
+        }
+    }
+}
+
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+struct SyncPoint {
+    /// The local time the beacon started
+    local_us: u64,
+    /// The slot counter carried by the beacon
+    slot_counter: u32,
+}
+
+/// Tracks the slot timing of a TDMA frame relative to the local clock
+///
+/// This holds all of the timing logic shared by [`TdmaMaster`] and [`TdmaNode`], and
+/// performs no radio access itself.
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct TdmaSchedule {
+    config: TdmaConfig,
+    sync: Option<SyncPoint>,
+    drift_ppm: Option<i32>,
+}
+
+impl TdmaSchedule {
+    /// Constructs a new, unsynchronised, [`TdmaSchedule`]
+    ///
+    /// Returns [`TdmaError::InvalidConfig`] if the configuration fails
+    /// [`TdmaConfig::validate`].
+    pub const fn new(config: TdmaConfig) -> Result<Self, TdmaError<Infallible>> {
+        if let Err(e) = config.validate() {
+            return Err(e);
+        }
+        Ok(Self {
+            config,
+            sync: None,
+            drift_ppm: None,
+        })
+    }
+
+    /// Returns the configuration of the frame
+    pub fn config(&self) -> &TdmaConfig {
+        &self.config
+    }
+
+    /// Returns `true` once a beacon has been observed
+    pub fn is_synced(&self) -> bool {
+        self.sync.is_some()
+    }
+
+    /// Returns the estimated drift of the local clock relative to the master, in parts per
+    /// million, once at least two beacons have been observed. A positive value means the
+    /// local clock runs fast.
+    pub fn drift_ppm(&self) -> Option<i32> {
+        self.drift_ppm
+    }
+
+    /// Records a beacon carrying `slot_counter` that started at local time `local_us`,
+    /// updating the drift estimate from the time elapsed since the previous beacon
+    pub fn on_beacon(&mut self, slot_counter: u32, local_us: u64) {
+        if let Some(prev) = self.sync {
+            let slots = slot_counter.wrapping_sub(prev.slot_counter) as i64;
+            let master_us = slots * self.config.slot_us() as i64;
+            let local_elapsed_us = local_us.wrapping_sub(prev.local_us) as i64;
+            if master_us > 0 && local_elapsed_us > 0 {
+                let measured = (local_elapsed_us - master_us) * PPM / master_us;
+                let measured = measured.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+                // Smooth the estimate to reject jitter in the beacon timestamps
+                self.drift_ppm = Some(match self.drift_ppm {
+                    Some(drift) => ((drift as i64 * 3 + measured as i64) / 4) as i32,
+                    None => measured,
+                });
+            }
+        }
+
+        self.sync = Some(SyncPoint {
+            local_us,
+            slot_counter,
+        });
+    }
+
+    /// Converts a duration on the master's clock to the local clock
+    fn master_to_local(&self, master_us: i64) -> i64 {
+        master_us + master_us * self.drift_ppm.unwrap_or(0) as i64 / PPM
+    }
+
+    /// Converts a duration on the local clock to the master's clock
+    fn local_to_master(&self, local_us: i64) -> i64 {
+        local_us * PPM / (PPM + self.drift_ppm.unwrap_or(0) as i64)
+    }
+
+    /// Returns the local time at which the slot with the given global counter begins
+    fn start_of(&self, sync: SyncPoint, slot_counter: u32) -> u64 {
+        let slots = slot_counter.wrapping_sub(sync.slot_counter) as i32 as i64;
+        let offset = self.master_to_local(slots * self.config.slot_us() as i64);
+        sync.local_us.wrapping_add_signed(offset)
+    }
+
+    /// Returns the global counter of the slot in progress at local time `now_us`
+    pub fn current_slot(&self, now_us: u64) -> Option<u32> {
+        let sync = self.sync?;
+        let elapsed = self.local_to_master(now_us.wrapping_sub(sync.local_us) as i64);
+        let slots = elapsed.div_euclid(self.config.slot_us() as i64);
+        Some(sync.slot_counter.wrapping_add(slots as u32))
+    }
+
+    /// Returns the global counter and local time at which the next occurrence of `slot`
+    /// may start transmitting, after its guard time, at or after local time `now_us`
+    pub fn next_slot(&self, slot: u16, now_us: u64) -> Option<(u32, u64)> {
+        let sync = self.sync?;
+        if slot >= self.config.slot_count {
+            return None;
+        }
+
+        let slot_count = self.config.slot_count as u32;
+        let current = self.current_slot(now_us)?;
+        let offset = (slot as u32 + slot_count - current % slot_count) % slot_count;
+        let mut counter = current.wrapping_add(offset);
+        loop {
+            let start = self.start_of(sync, counter) + self.config.guard_us as u64;
+            if start >= now_us {
+                return Some((counter, start));
+            }
+            counter = counter.wrapping_add(slot_count);
+        }
+    }
+}
+
+/// Sleeps until the local clock reaches `target_us`
+#[maybe_async::maybe_async]
+async fn wait_until<D: DelayNs, C: Monotonic>(delay: &mut D, clock: &C, target_us: u64) {
+    let now_us = clock.now_us();
+    if target_us > now_us {
+        let wait_us = (target_us - now_us).min(u32::MAX as u64) as u32;
+        delay.delay_us(wait_us).await;
+    }
+}
+
+/// Waits for the given slot and receives a single packet within it
+#[maybe_async::maybe_async]
+async fn receive_in_slot<SPI: SpiDevice, D: DelayNs, C: Monotonic>(
+    schedule: &TdmaSchedule,
+    radio: &mut A7105<SPI>,
+    delay: &mut D,
+    clock: &C,
+    slot: u16,
+    buf: &mut [u8],
+) -> Result<u32, TdmaError<SPI::Error>> {
+    let config = schedule.config;
+    let (counter, start_us) = schedule
+        .next_slot(slot, clock.now_us())
+        .ok_or(TdmaError::InvalidSlot)?;
+
+    radio
+        .write_reg(Pll1 {
+            channel: config.data_channel,
+        })
+        .await?;
+    wait_until(delay, clock, start_us).await;
+    let window_us = config.slot_us().saturating_sub(config.guard_us);
+    radio.receive(buf, delay, window_us).await?;
+    Ok(counter)
+}
+
+/// Waits for the given slot and transmits a single packet within it
+///
+/// Slot 0 is reserved for the beacon, so is rejected with [`TdmaError::InvalidSlot`].
+#[maybe_async::maybe_async]
+async fn transmit_in_slot<SPI: SpiDevice, D: DelayNs, C: Monotonic>(
+    schedule: &TdmaSchedule,
+    radio: &mut A7105<SPI>,
+    delay: &mut D,
+    clock: &C,
+    slot: u16,
+    buf: &[u8],
+) -> Result<u32, TdmaError<SPI::Error>> {
+    if slot == 0 {
+        return Err(TdmaError::InvalidSlot);
+    }
+    let (counter, start_us) = schedule
+        .next_slot(slot, clock.now_us())
+        .ok_or(TdmaError::InvalidSlot)?;
+
+    radio
+        .write_reg(Pll1 {
+            channel: schedule.config.data_channel,
+        })
+        .await?;
+    wait_until(delay, clock, start_us).await;
+    radio.transmit(buf, delay).await?;
+    Ok(counter)
+}
+
+/// The radio that owns the TDMA frame timing, sending beacons with `N` byte packets
+pub struct TdmaMaster<const N: usize> {
+    schedule: TdmaSchedule,
+    next_counter: u32,
+}
+
+impl<const N: usize> TdmaMaster<N> {
+    /// Fails to compile when a beacon does not fit into an `N` byte packet
+    const BEACON_FITS: () = assert!(N >= BEACON_LEN, "TDMA packets must be at least 5 bytes");
+
+    /// Constructs a new [`TdmaMaster`] that has not yet sent any beacons
+    ///
+    /// Returns [`TdmaError::InvalidConfig`] if the configuration fails
+    /// [`TdmaConfig::validate`]. Using packets shorter than 5 bytes is a compile time error.
+    pub fn new(config: TdmaConfig) -> Result<Self, TdmaError<Infallible>> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::BEACON_FITS;
+        Ok(Self {
+            schedule: TdmaSchedule::new(config)?,
+            next_counter: 0,
+        })
+    }
+
+    /// Returns the slot timing of the frame
+    pub fn schedule(&self) -> &TdmaSchedule {
+        &self.schedule
+    }
+
+    /// Waits for the start of the next frame and transmits its beacon, returning the slot
+    /// counter it carried
+    ///
+    /// The first call transmits immediately, establishing the frame timing.
+    #[maybe_async::maybe_async]
+    pub async fn send_beacon<SPI: SpiDevice, D: DelayNs, C: Monotonic>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        clock: &C,
+    ) -> Result<u32, TdmaError<SPI::Error>> {
+        let config = self.schedule.config;
+        let counter = self.next_counter;
+        let start_us = match self.schedule.sync {
+            Some(sync) => self.schedule.start_of(sync, counter),
+            None => clock.now_us(),
+        };
+
+        let mut frame = [0; N];
+        encode_beacon(counter, &mut frame);
+        radio
+            .write_reg(Pll1 {
+                channel: config.beacon_channel,
+            })
+            .await?;
+        wait_until(delay, clock, start_us).await;
+        radio.transmit(&frame, delay).await?;
+
+        // The master's own clock is the reference, so it never drifts
+        self.schedule.sync = Some(SyncPoint {
+            local_us: start_us,
+            slot_counter: counter,
+        });
+        self.next_counter = counter.wrapping_add(config.slot_count as u32);
+        Ok(counter)
+    }
+
+    /// Waits for the next occurrence of `slot` and receives a packet within it, returning
+    /// the global slot counter the packet was received in
+    #[maybe_async::maybe_async]
+    pub async fn receive_in_slot<SPI: SpiDevice, D: DelayNs, C: Monotonic>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        clock: &C,
+        slot: u16,
+        buf: &mut [u8],
+    ) -> Result<u32, TdmaError<SPI::Error>> {
+        if !self.schedule.is_synced() {
+            return Err(TdmaError::NotSynced);
+        }
+        receive_in_slot(&self.schedule, radio, delay, clock, slot, buf).await
+    }
+
+    /// Waits for the next occurrence of `slot` and transmits a packet within it, returning
+    /// the global slot counter the packet was sent in
+    ///
+    /// Slot 0 is reserved for the beacon, so is rejected with [`TdmaError::InvalidSlot`].
+    #[maybe_async::maybe_async]
+    pub async fn transmit_in_slot<SPI: SpiDevice, D: DelayNs, C: Monotonic>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        clock: &C,
+        slot: u16,
+        buf: &[u8],
+    ) -> Result<u32, TdmaError<SPI::Error>> {
+        if !self.schedule.is_synced() {
+            return Err(TdmaError::NotSynced);
+        }
+        transmit_in_slot(&self.schedule, radio, delay, clock, slot, buf).await
+    }
+}
+
+/// A radio that follows the frame timing of a [`TdmaMaster`] with `N` byte packets
+pub struct TdmaNode<const N: usize> {
+    schedule: TdmaSchedule,
+}
+
+impl<const N: usize> TdmaNode<N> {
+    /// Fails to compile when a beacon does not fit into an `N` byte packet
+    const BEACON_FITS: () = assert!(N >= BEACON_LEN, "TDMA packets must be at least 5 bytes");
+
+    /// Constructs a new [`TdmaNode`] that is not yet synchronised to a master
+    ///
+    /// Returns [`TdmaError::InvalidConfig`] if the configuration fails
+    /// [`TdmaConfig::validate`]. Using packets shorter than 5 bytes is a compile time error.
+    pub fn new(config: TdmaConfig) -> Result<Self, TdmaError<Infallible>> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::BEACON_FITS;
+        Ok(Self {
+            schedule: TdmaSchedule::new(config)?,
+        })
+    }
+
+    /// Returns the slot timing of the frame
+    pub fn schedule(&self) -> &TdmaSchedule {
+        &self.schedule
+    }
+
+    /// Listens on the beacon channel for up to `timeout_us` microseconds, synchronising to
+    /// the first beacon received and returning its slot counter
+    ///
+    /// Once synchronised, the listen window begins a guard time before the next expected
+    /// beacon rather than immediately. Corrupt packets and packets other than beacons are
+    /// ignored, without extending the wait.
+    #[maybe_async::maybe_async]
+    pub async fn sync<SPI: SpiDevice, D: DelayNs, C: Monotonic>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        clock: &C,
+        timeout_us: u32,
+    ) -> Result<u32, TdmaError<SPI::Error>> {
+        let config = self.schedule.config;
+        radio
+            .write_reg(Pll1 {
+                channel: config.beacon_channel,
+            })
+            .await?;
+
+        if let Some((_, start_us)) = self.schedule.next_slot(0, clock.now_us()) {
+            let early_us = 2 * config.guard_us as u64;
+            wait_until(delay, clock, start_us.saturating_sub(early_us)).await;
+        }
+
+        let mut frame = [0; N];
+        let mut remaining_us = timeout_us;
+        loop {
+            match radio
+                .receive_within(&mut frame, delay, &mut remaining_us)
+                .await
+            {
+                Ok(()) => {}
+                Err(ReadPacketError::PacketError(_)) => continue,
+                Err(e) => return Err(e.into()),
+            }
+            let received_us = clock.now_us();
+            if let Some(counter) = decode_beacon(&frame) {
+                let start_us = received_us.saturating_sub(config.beacon_latency_us as u64);
+                self.schedule.on_beacon(counter, start_us);
+                return Ok(counter);
+            }
+        }
+    }
+
+    /// Waits for the next occurrence of `slot` and receives a packet within it, returning
+    /// the global slot counter the packet was received in
+    #[maybe_async::maybe_async]
+    pub async fn receive_in_slot<SPI: SpiDevice, D: DelayNs, C: Monotonic>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        clock: &C,
+        slot: u16,
+        buf: &mut [u8],
+    ) -> Result<u32, TdmaError<SPI::Error>> {
+        if !self.schedule.is_synced() {
+            return Err(TdmaError::NotSynced);
+        }
+        receive_in_slot(&self.schedule, radio, delay, clock, slot, buf).await
+    }
+
+    /// Waits for the next occurrence of `slot` and transmits a packet within it, returning
+    /// the global slot counter the packet was sent in
+    ///
+    /// Slot 0 is reserved for the beacon, so is rejected with [`TdmaError::InvalidSlot`].
+    #[maybe_async::maybe_async]
+    pub async fn transmit_in_slot<SPI: SpiDevice, D: DelayNs, C: Monotonic>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        clock: &C,
+        slot: u16,
+        buf: &[u8],
+    ) -> Result<u32, TdmaError<SPI::Error>> {
+        if !self.schedule.is_synced() {
+            return Err(TdmaError::NotSynced);
+        }
+        transmit_in_slot(&self.schedule, radio, delay, clock, slot, buf).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::{run, MockDelay, MockSpi};
+
+    struct MockClock(u64);
+
+    impl Monotonic for MockClock {
+        fn now_us(&self) -> u64 {
+            self.0
+        }
+    }
+
+    fn config() -> TdmaConfig {
+        TdmaConfig {
+            frame_us: 10_000,
+            slot_count: 10,
+            guard_us: 100,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_beacon_round_trip() {
+        let mut frame = [0xFF; 8];
+        encode_beacon(0x1234_5678, &mut frame);
+        assert_eq!(decode_beacon(&frame), Some(0x1234_5678));
+        assert_eq!(decode_beacon(&[0; 8]), None);
+        assert_eq!(decode_beacon(&[BEACON_MAGIC, 0]), None);
+    }
+
+    #[test]
+    fn test_slot_timing() {
+        let mut schedule = TdmaSchedule::new(config()).unwrap();
+        assert_eq!(schedule.next_slot(1, 0), None);
+
+        schedule.on_beacon(20, 50_000);
+        assert_eq!(schedule.current_slot(50_000), Some(20));
+        assert_eq!(schedule.current_slot(52_500), Some(22));
+
+        // Slot 3 of the current frame, after its guard time
+        assert_eq!(schedule.next_slot(3, 50_000), Some((23, 53_100)));
+        // Slot 3 has already started, so the next frame's is used
+        assert_eq!(schedule.next_slot(3, 53_200), Some((33, 63_100)));
+        // Slot 0 of the next frame
+        assert_eq!(schedule.next_slot(0, 51_000), Some((30, 60_100)));
+
+        assert_eq!(schedule.next_slot(10, 50_000), None);
+    }
+
+    #[test]
+    fn test_drift_compensation() {
+        let mut schedule = TdmaSchedule::new(config()).unwrap();
+        schedule.on_beacon(0, 1_000_000);
+        assert_eq!(schedule.drift_ppm(), None);
+
+        // 100 frames later the local clock has counted an extra 100us, which is 100ppm fast
+        schedule.on_beacon(1_000, 2_000_100);
+        assert_eq!(schedule.drift_ppm(), Some(100));
+
+        // Slots are stretched by the drift when predicting their start on the local clock
+        assert_eq!(schedule.next_slot(0, 2_000_200), Some((1_000, 2_000_200)));
+        assert_eq!(schedule.next_slot(0, 2_000_201), Some((1_010, 2_010_201)));
+
+        // A slot almost a full second away is predicted 90us later on the local clock
+        let (_, start) = schedule.next_slot(0, 2_900_000).unwrap();
+        assert_eq!(start, 2_000_100 + 900_090 + 100);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(TdmaConfig::new(10_000, 10).is_ok());
+        assert!(TdmaConfig::new(10, 10).is_ok());
+        assert_eq!(TdmaConfig::new(10_000, 0), Err(TdmaError::InvalidConfig));
+        assert_eq!(TdmaConfig::new(9, 10), Err(TdmaError::InvalidConfig));
+
+        // Configurations built from the public fields are checked by the constructors
+        let config = TdmaConfig {
+            slot_count: 0,
+            ..config()
+        };
+        assert_eq!(TdmaSchedule::new(config), Err(TdmaError::InvalidConfig));
+        assert!(TdmaMaster::<8>::new(config).is_err());
+        assert!(TdmaNode::<8>::new(config).is_err());
+    }
+
+    #[test]
+    fn test_beacon_slot_reserved() {
+        let spi = MockSpi::new();
+        let mut radio = A7105::new(spi.clone());
+        let mut delay = MockDelay::default();
+        let clock = MockClock(0);
+        let mut master = TdmaMaster::<8>::new(config()).unwrap();
+        run!(master.send_beacon(&mut radio, &mut delay, &clock)).unwrap();
+        assert_eq!(spi.sim().sent.len(), 1);
+
+        let result = run!(master.transmit_in_slot(&mut radio, &mut delay, &clock, 0, &[1; 8]));
+        assert_eq!(result, Err(TdmaError::InvalidSlot));
+        assert_eq!(spi.sim().sent.len(), 1);
+
+        let result = run!(master.transmit_in_slot(&mut radio, &mut delay, &clock, 1, &[1; 8]));
+        assert_eq!(result, Ok(1));
+        assert_eq!(spi.sim().sent.len(), 2);
+    }
+
+    #[test]
+    fn test_sync_timeout_spans_other_frames() {
+        let spi = MockSpi::new();
+        let mut radio = A7105::new(spi.clone());
+        let mut delay = MockDelay::default();
+        let clock = MockClock(0);
+        let mut node = TdmaNode::<8>::new(config()).unwrap();
+
+        // Data traffic on the shared channel does not restart the timeout
+        spi.sim().airtime_polls = 5;
+        for _ in 0..100 {
+            spi.sim().push(&[1; 8]);
+        }
+        let result = run!(node.sync(&mut radio, &mut delay, &clock, 1_000));
+        assert_eq!(result, Err(TdmaError::Timeout));
+        assert_eq!(delay.elapsed_us, 1_000);
+        assert!(!node.schedule().is_synced());
+
+        // A corrupt packet does not end the wait for a beacon
+        let spi = MockSpi::new();
+        let mut radio = A7105::new(spi.clone());
+        let mut beacon = [0; 8];
+        encode_beacon(40, &mut beacon);
+        spi.sim().push_corrupt(&beacon);
+        spi.sim().push(&[1; 8]);
+        spi.sim().push(&beacon);
+        let result = run!(node.sync(&mut radio, &mut delay, &clock, 1_000));
+        assert_eq!(result, Ok(40));
+        assert!(node.schedule().is_synced());
+    }
+}
@@ -0,0 +1,282 @@
+//! A bit-banged 3-wire SPI transport for the A7105
+//!
+//! Out of reset the A7105 uses 3-wire SPI, where SDIO carries both the data written by the
+//! host and the data read back from the A7105. Many modules only break out SDIO, which most
+//! SPI peripherals cannot drive half duplex, so [`ThreeWireSpi`] bit-bangs the bus over
+//! plain GPIO pins instead and implements `SpiDevice` for use with [`A7105`](crate::A7105).
+//!
+//! The SDIO pin must be configured as an open drain output with a pull-up, so that it can be
+//! released by driving it high and then read while the A7105 drives it. Timing is derived
+//! from a blocking delay, so the bus is slow but never faster than the A7105 can follow.
+//!
+//! Modules that also break out GIO1 or GIO2 can instead use a SPI peripheral directly, by
+//! switching the A7105 to 4-wire SPI through
+//! [`A7105::enable_four_wire`](crate::A7105::enable_four_wire) before reading any register.
+//!
+//! ```ignore
+//! use a7105::prelude::*;
+//! use a7105::three_wire::ThreeWireSpi;
+//!
+//! # let (cs, sck, sdio, bit_delay) = unimplemented!();
+//! let spi = ThreeWireSpi::new(cs, sck, sdio, bit_delay, 500);
+//! let mut radio = A7105::new(spi);
+//!
+//! let mode: registers::Mode = radio.read_reg().await.unwrap();
+//! ```
+
+use crate::ThreeWireError;
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    spi::{ErrorType, Operation},
+};
+
+/// A half duplex SPI device bit-banged over a chip select, clock and shared data pin
+///
+/// Refer to the [module level documentation](self) for an overview.
+pub struct ThreeWireSpi<CS, SCK, SDIO, D> {
+    cs: CS,
+    sck: SCK,
+    sdio: SDIO,
+    delay: D,
+    half_period_ns: u32,
+}
+
+impl<CS, SCK, SDIO, D, E> ThreeWireSpi<CS, SCK, SDIO, D>
+where
+    CS: OutputPin<Error = E>,
+    SCK: OutputPin<Error = E>,
+    SDIO: OutputPin<Error = E> + InputPin<Error = E>,
+    D: DelayNs,
+{
+    /// Constructs a new [`ThreeWireSpi`] clocking each bit over `2 * half_period_ns`
+    ///
+    /// The pins are not touched until the first transaction, so CS should already be high
+    /// and SCK low.
+    pub fn new(cs: CS, sck: SCK, sdio: SDIO, delay: D, half_period_ns: u32) -> Self {
+        Self {
+            cs,
+            sck,
+            sdio,
+            delay,
+            half_period_ns,
+        }
+    }
+
+    /// Destroys this [`ThreeWireSpi`], returning the pins and delay
+    pub fn release(self) -> (CS, SCK, SDIO, D) {
+        (self.cs, self.sck, self.sdio, self.delay)
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), E> {
+        for bit in (0..8).rev() {
+            if byte & (1 << bit) != 0 {
+                self.sdio.set_high()?;
+            } else {
+                self.sdio.set_low()?;
+            }
+            self.delay.delay_ns(self.half_period_ns);
+            // The A7105 latches SDIO on the rising edge
+            self.sck.set_high()?;
+            self.delay.delay_ns(self.half_period_ns);
+            self.sck.set_low()?;
+        }
+        Ok(())
+    }
+
+    fn read_byte(&mut self) -> Result<u8, E> {
+        let mut byte = 0;
+        for _ in 0..8 {
+            // The A7105 shifts data out on the falling edge, so it is stable by the rising edge
+            self.delay.delay_ns(self.half_period_ns);
+            self.sck.set_high()?;
+            byte = byte << 1 | u8::from(self.sdio.is_high()?);
+            self.delay.delay_ns(self.half_period_ns);
+            self.sck.set_low()?;
+        }
+        Ok(byte)
+    }
+
+    fn run(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), ThreeWireError<E>> {
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    for byte in bytes.iter() {
+                        self.write_byte(*byte)?;
+                    }
+                }
+                Operation::Read(buf) => {
+                    // Release SDIO so the A7105 can drive it
+                    self.sdio.set_high()?;
+                    for byte in buf.iter_mut() {
+                        *byte = self.read_byte()?;
+                    }
+                }
+                Operation::Transfer(..) | Operation::TransferInPlace(..) => {
+                    return Err(ThreeWireError::FullDuplex)
+                }
+                Operation::DelayNs(ns) => self.delay.delay_ns(*ns),
+            }
+        }
+        Ok(())
+    }
+
+    fn transaction_inner(
+        &mut self,
+        operations: &mut [Operation<'_, u8>],
+    ) -> Result<(), ThreeWireError<E>> {
+        self.sck.set_low()?;
+        self.cs.set_low()?;
+        let result = self.run(operations);
+        // Always deselect the A7105, but report the first error encountered
+        let deselect = self.cs.set_high().and_then(|()| self.sdio.set_high());
+        result?;
+        Ok(deselect?)
+    }
+}
+
+impl<CS, SCK, SDIO, D, E> ErrorType for ThreeWireSpi<CS, SCK, SDIO, D>
+where
+    CS: OutputPin<Error = E>,
+    SCK: OutputPin<Error = E>,
+    SDIO: OutputPin<Error = E> + InputPin<Error = E>,
+    D: DelayNs,
+    E: core::fmt::Debug,
+{
+    type Error = ThreeWireError<E>;
+}
+
+#[cfg(feature = "blocking")]
+impl<CS, SCK, SDIO, D, E> embedded_hal::spi::SpiDevice for ThreeWireSpi<CS, SCK, SDIO, D>
+where
+    CS: OutputPin<Error = E>,
+    SCK: OutputPin<Error = E>,
+    SDIO: OutputPin<Error = E> + InputPin<Error = E>,
+    D: DelayNs,
+    E: core::fmt::Debug,
+{
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        self.transaction_inner(operations)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<CS, SCK, SDIO, D, E> embedded_hal_async::spi::SpiDevice for ThreeWireSpi<CS, SCK, SDIO, D>
+where
+    CS: OutputPin<Error = E>,
+    SCK: OutputPin<Error = E>,
+    SDIO: OutputPin<Error = E> + InputPin<Error = E>,
+    D: DelayNs,
+    E: core::fmt::Debug,
+{
+    async fn transaction(
+        &mut self,
+        operations: &mut [Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        self.transaction_inner(operations)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::{cell::RefCell, convert::Infallible};
+    use embedded_hal::digital::ErrorType as PinErrorType;
+
+    /// A minimal model of the A7105 end of the bus, recording written bits and shifting
+    /// out a fixed byte once 8 bits have been received
+    #[derive(Default)]
+    struct Bus {
+        selected: bool,
+        sck: bool,
+        sdio_host: bool,
+        written: u16,
+        bits: u8,
+        response: u8,
+    }
+
+    impl Bus {
+        fn sdio(&self) -> bool {
+            // The response is shifted out once the 8 address bits have been clocked in, the
+            // first bit being sampled on the 9th rising edge
+            match self.bits.checked_sub(9) {
+                Some(bit) => self.response & (0x80 >> bit) != 0,
+                None => self.sdio_host,
+            }
+        }
+    }
+
+    struct Pin<'a>(&'a RefCell<Bus>, u8);
+
+    impl PinErrorType for Pin<'_> {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for Pin<'_> {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            let mut bus = self.0.borrow_mut();
+            match self.1 {
+                0 => bus.selected = true,
+                1 => bus.sck = false,
+                _ => bus.sdio_host = false,
+            }
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            let mut bus = self.0.borrow_mut();
+            match self.1 {
+                0 => bus.selected = false,
+                1 => {
+                    if bus.selected && !bus.sck {
+                        if bus.bits < 8 {
+                            bus.written = bus.written << 1 | u16::from(bus.sdio_host);
+                        }
+                        bus.bits += 1;
+                    }
+                    bus.sck = true;
+                }
+                _ => bus.sdio_host = true,
+            }
+            Ok(())
+        }
+    }
+
+    impl InputPin for Pin<'_> {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            Ok(self.0.borrow().sdio())
+        }
+
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            self.is_high().map(|high| !high)
+        }
+    }
+
+    struct NoDelay;
+
+    impl DelayNs for NoDelay {
+        fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    #[test]
+    fn test_read_register() {
+        let bus = RefCell::new(Bus {
+            response: 0xA5,
+            ..Default::default()
+        });
+        let mut spi = ThreeWireSpi::new(Pin(&bus, 0), Pin(&bus, 1), Pin(&bus, 2), NoDelay, 0);
+
+        let mut buf = [0];
+        spi.transaction_inner(&mut [Operation::Write(&[0x4E]), Operation::Read(&mut buf)])
+            .unwrap();
+        assert_eq!(bus.borrow().written, 0x4E);
+        assert_eq!(buf, [0xA5]);
+        assert!(!bus.borrow().selected);
+
+        assert_eq!(
+            spi.transaction_inner(&mut [Operation::TransferInPlace(&mut buf)]),
+            Err(ThreeWireError::FullDuplex)
+        );
+        assert!(!bus.borrow().selected);
+    }
+}
@@ -0,0 +1,20 @@
+//! Time keeping primitives used by the scheduling parts of this crate
+
+/// A source of monotonically increasing timestamps
+///
+/// This is intentionally minimal so that it can be implemented on top of any timer, such as
+/// `embassy_time::Instant` or an RTIC `Monotonic`.
+///
+/// ```ignore
+/// struct EmbassyClock;
+///
+/// impl a7105::time::Monotonic for EmbassyClock {
+///     fn now_us(&self) -> u64 {
+///         embassy_time::Instant::now().as_micros()
+///     }
+/// }
+/// ```
+pub trait Monotonic {
+    /// Returns the current time in microseconds
+    fn now_us(&self) -> u64;
+}
@@ -0,0 +1,177 @@
+//! Duty-cycled receive for battery powered nodes
+//!
+//! A [`WakeOnRx`] keeps the A7105 in [`Mode::Sleep`] for most of the time, periodically
+//! waking it up to listen for a packet for a short window. GIO1 is configured to output
+//! the WTR (wait until TX or RX finished) signal, which drops as soon as a packet has been
+//! received, so the host only needs to watch that pin during each window.
+//!
+//! Early revisions of the A7105 also had an on-chip timed wake function driven by the
+//! internal RC oscillator. That function was removed from the datasheet and its registers
+//! are now reserved for internal usage, so the sleep period is timed by the host instead.
+//!
+//! ```ignore
+//! use a7105::prelude::*;
+//! use a7105::wake_on_rx::{WakeOnRx, WakeOnRxConfig};
+//!
+//! # let (a7105_spi_peripheral, mut delay, gio1) = unimplemented!();
+//! let mut radio = A7105::new(a7105_spi_peripheral);
+//! let mut wor = WakeOnRx::new(WakeOnRxConfig::default(), gio1);
+//! wor.configure(&mut radio).await.unwrap();
+//!
+//! let mut buf = [0; 16];
+//! let cycles = wor.listen(&mut radio, &mut delay, &mut buf).await.unwrap();
+//! ```
+
+use crate::{commands::Mode, registers, ReadPacketError, WakeError, A7105};
+use defmt::Format;
+
+#[cfg(feature = "blocking")]
+use embedded_hal::{delay::DelayNs, digital::InputPin as WtrPin, spi::SpiDevice};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs, digital::Wait as WtrPin, spi::SpiDevice};
+
+/// The time given to the A7105 to raise WTR after being placed in [`Mode::Rx`]
+const WTR_SETTLE_US: u32 = 50;
+
+/// Waits up to `timeout_us` microseconds for WTR to drop, returning `false` on timeout
+#[cfg(feature = "blocking")]
+fn wait_for_wtr<P: WtrPin, D: DelayNs>(
+    wtr: &mut P,
+    delay: &mut D,
+    timeout_us: u32,
+) -> Result<bool, P::Error> {
+    const POLL_INTERVAL_US: u32 = 50;
+
+    let mut waited_us = 0;
+    while wtr.is_high()? {
+        if waited_us >= timeout_us {
+            return Ok(false);
+        }
+        delay.delay_us(POLL_INTERVAL_US);
+        waited_us += POLL_INTERVAL_US;
+    }
+    Ok(true)
+}
+
+/// Waits up to `timeout_us` microseconds for WTR to drop, returning `false` on timeout
+#[cfg(feature = "async")]
+async fn wait_for_wtr<P: WtrPin, D: DelayNs>(
+    wtr: &mut P,
+    delay: &mut D,
+    timeout_us: u32,
+) -> Result<bool, P::Error> {
+    use core::future::{poll_fn, Future};
+    use core::pin::pin;
+    use core::task::Poll;
+
+    let mut low = pin!(wtr.wait_for_low());
+    let mut timeout = pin!(delay.delay_us(timeout_us));
+    poll_fn(|cx| {
+        if let Poll::Ready(result) = low.as_mut().poll(cx) {
+            return Poll::Ready(result.map(|()| true));
+        }
+        timeout.as_mut().poll(cx).map(|()| Ok(false))
+    })
+    .await
+}
+
+/// Configuration for a [`WakeOnRx`]
+#[derive(Format, PartialEq, Debug, Copy, Clone)]
+pub struct WakeOnRxConfig {
+    /// How long the A7105 sleeps between receive windows, in microseconds
+    pub sleep_us: u32,
+    /// How long the A7105 listens for a packet after waking up, in microseconds
+    ///
+    /// This must cover the PLL settling time as well as the preamble and ID of the packet,
+    /// so transmitters should repeat their packet for at least
+    /// `sleep_us + xtal_settling + rx_window_us` to be heard.
+    pub rx_window_us: u32,
+    /// The crystal settling delay configured through [`Delay2`](registers::Delay2), which
+    /// is waited out in [`Mode::Standby`] after every sleep before entering [`Mode::Rx`]
+    pub xtal_settling: registers::XtalSettlingDelay,
+}
+
+impl Default for WakeOnRxConfig {
+    fn default() -> Self {
+        Self {
+            sleep_us: 100_000,
+            rx_window_us: 2_000,
+            xtal_settling: registers::Delay2::default().xtal_settling_delay,
+        }
+    }
+}
+
+/// A duty-cycled receiver that watches the WTR signal on GIO1
+///
+/// Refer to the [module level documentation](self) for an overview.
+pub struct WakeOnRx<P> {
+    config: WakeOnRxConfig,
+    wtr: P,
+}
+
+impl<P: WtrPin> WakeOnRx<P> {
+    /// Constructs a new [`WakeOnRx`] watching the provided pin, which must be connected to
+    /// GIO1 of the A7105
+    pub const fn new(config: WakeOnRxConfig, wtr: P) -> Self {
+        Self { config, wtr }
+    }
+
+    /// Destroys this [`WakeOnRx`], returning the WTR pin
+    pub fn release(self) -> P {
+        self.wtr
+    }
+
+    /// Configures GIO1 of the A7105 to output the WTR signal
+    #[maybe_async::maybe_async]
+    pub async fn configure<SPI: SpiDevice>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+    ) -> Result<(), SPI::Error> {
+        radio
+            .write_reg(registers::Gpio1PinControl {
+                pin_function: registers::GpioPinFunction::Wtr,
+                invert_output: false,
+                output_enabled: true,
+            })
+            .await
+    }
+
+    /// Duty cycles the A7105 until a valid packet is received, writing it into the provided
+    /// buffer and returning the number of receive windows that elapsed without one
+    ///
+    /// Corrupted packets are discarded and do not end the wait. The A7105 is left in
+    /// [`Mode::Standby`] once a packet has been received.
+    #[maybe_async::maybe_async]
+    pub async fn listen<SPI: SpiDevice, D: DelayNs>(
+        &mut self,
+        radio: &mut A7105<SPI>,
+        delay: &mut D,
+        buf: &mut [u8],
+    ) -> Result<u32, WakeError<SPI::Error, P::Error>> {
+        let mut cycles = 0;
+        loop {
+            radio.set_mode(Mode::Rx).await?;
+            delay.delay_us(WTR_SETTLE_US).await;
+
+            let window_us = self.config.rx_window_us.saturating_sub(WTR_SETTLE_US);
+            let received = wait_for_wtr(&mut self.wtr, delay, window_us)
+                .await
+                .map_err(WakeError::PinError)?;
+
+            if received {
+                match radio.rx(buf).await {
+                    Ok(()) => return Ok(cycles),
+                    Err(ReadPacketError::SpiError(e)) => return Err(e.into()),
+                    Err(ReadPacketError::PacketError(_) | ReadPacketError::Timeout) => {}
+                }
+            }
+
+            radio.set_mode(Mode::Sleep).await?;
+            delay.delay_us(self.config.sleep_us).await;
+            // The crystal is stopped while sleeping, so must settle before the PLL can lock
+            radio.set_mode(Mode::Standby).await?;
+            delay.delay_us(self.config.xtal_settling.us()).await;
+            cycles += 1;
+        }
+    }
+}